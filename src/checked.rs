@@ -0,0 +1,154 @@
+use std::{
+    fmt,
+    ops::{Add, Mul},
+};
+
+// Arithmetic used to accumulate answers. Plain operators by default, with the
+// `checked` feature enabled every step is checked and an overflow stops the
+// solve with an error naming the day and part rather than wrapping silently.
+//
+// Checked mode aborts: parts return their answer as a plain String, so the
+// Overflow is raised as a panic carrying its message instead of being
+// returned. Nothing catches it, the run ends at the first overflow.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow {
+    pub day: u8,
+    pub part: u8,
+}
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Arithmetic overflow in day {:02} part {}",
+            self.day, self.part
+        )
+    }
+}
+impl std::error::Error for Overflow {}
+
+pub trait CheckedNum: Copy + Add<Output = Self> + Mul<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_num {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedNum for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+impl_checked_num!(i32, i64, i128, isize, u32, u64, u128, usize);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Accumulator {
+    day: u8,
+    part: u8,
+}
+impl Accumulator {
+    pub fn new(day: u8, part: u8) -> Self {
+        Accumulator { day, part }
+    }
+
+    #[cfg(feature = "checked")]
+    pub fn add<T: CheckedNum>(&self, a: T, b: T) -> T {
+        a.checked_add(b).unwrap_or_else(|| self.overflow())
+    }
+
+    #[cfg(not(feature = "checked"))]
+    pub fn add<T: CheckedNum>(&self, a: T, b: T) -> T {
+        a + b
+    }
+
+    #[cfg(feature = "checked")]
+    pub fn mul<T: CheckedNum>(&self, a: T, b: T) -> T {
+        a.checked_mul(b).unwrap_or_else(|| self.overflow())
+    }
+
+    #[cfg(not(feature = "checked"))]
+    pub fn mul<T: CheckedNum>(&self, a: T, b: T) -> T {
+        a * b
+    }
+
+    pub fn pow<T: CheckedNum>(&self, base: T, exp: u32) -> T {
+        (0..exp).fold(T::ONE, |acc, _| self.mul(acc, base))
+    }
+
+    pub fn sum<T, I>(&self, values: I) -> T
+    where
+        T: CheckedNum,
+        I: IntoIterator<Item = T>,
+    {
+        values.into_iter().fold(T::ZERO, |acc, x| self.add(acc, x))
+    }
+
+    pub fn product<T, I>(&self, values: I) -> T
+    where
+        T: CheckedNum,
+        I: IntoIterator<Item = T>,
+    {
+        values.into_iter().fold(T::ONE, |acc, x| self.mul(acc, x))
+    }
+
+    // Converts between integer types, e.g. widening a rank before multiplying.
+    pub fn widen<T, U>(&self, value: T) -> U
+    where
+        U: TryFrom<T>,
+    {
+        U::try_from(value).unwrap_or_else(|_| self.overflow())
+    }
+
+    // Aborts the run, see the note at the top of the module.
+    fn overflow(&self) -> ! {
+        panic!(
+            "{}",
+            Overflow {
+                day: self.day,
+                part: self.part,
+            }
+        )
+    }
+}
+
+pub trait AccumulateExt: Iterator + Sized {
+    fn sum_with(self, acc: &Accumulator) -> Self::Item
+    where
+        Self::Item: CheckedNum,
+    {
+        acc.sum(self)
+    }
+
+    fn product_with(self, acc: &Accumulator) -> Self::Item
+    where
+        Self::Item: CheckedNum,
+    {
+        acc.product(self)
+    }
+}
+impl<I: Iterator> AccumulateExt for I {}
+
+#[cfg(all(test, feature = "checked"))]
+mod tests {
+    use crate::checked::Accumulator;
+
+    #[test]
+    #[should_panic(expected = "Arithmetic overflow in day 07 part 1")]
+    fn validate_overflow_is_reported() {
+        Accumulator::new(7, 1).sum([i32::MAX, 1]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::checked::{Accumulator, AccumulateExt};
 use crate::Solution;
 
 #[derive(Clone, Debug)]
@@ -13,7 +14,7 @@ impl Solution for Day00 {
     fn part_one(parsed_input: &mut Self::ParsedInput) -> String {
         format!(
             "{}",
-            parsed_input.lines().map(sum_numbers_in_line).sum::<i32>()
+            parsed_input.lines().map(sum_numbers_in_line).sum_with(&Accumulator::new(0, 1))
         )
     }
 
@@ -23,7 +24,7 @@ impl Solution for Day00 {
             parsed_input
                 .lines()
                 .map(square_difference_in_line)
-                .sum::<i32>()
+                .sum_with(&Accumulator::new(0, 2))
         )
     }
 }
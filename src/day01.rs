@@ -1,3 +1,8 @@
+use std::fmt;
+use std::io::IsTerminal;
+
+use crate::checked::Accumulator;
+use crate::report::{json_string, ExplainError, OutputFormat, HIGHLIGHT, RESET};
 use crate::Solution;
 
 #[derive(Clone, Debug)]
@@ -14,52 +19,306 @@ impl Solution for Day01 {
             .collect::<Vec<String>>()
     }
 
-    fn part_one(_parsed_input: &mut Self::ParsedInput) -> String {
-        let mut total = 0;
-        for line in _parsed_input {
-            let just_nums = get_nums(line);
-            total += calc_calibration(&just_nums);
-        }
-        total.to_string()
+    fn part_one(parsed_input: &mut Self::ParsedInput) -> String {
+        total_calibration(parsed_input, &Vocabulary::digits_only(), Accumulator::new(1, 1))
+            .to_string()
     }
 
     fn part_two(parsed_input: &mut Self::ParsedInput) -> String {
-        let mut total = 0;
-        for line in parsed_input {
-            let line = replace_num_word(line);
-            let just_nums = get_nums(&line);
-            total += calc_calibration(&just_nums);
+        total_calibration(parsed_input, &Vocabulary::english(), Accumulator::new(1, 2))
+            .to_string()
+    }
+}
+
+impl Day01 {
+    // Per-line breakdown of which digits each part picked up.
+    pub fn explain(input_lines: &str) -> Vec<LineExplanation> {
+        let part_one = Vocabulary::digits_only();
+        let part_two = Vocabulary::english();
+        input_lines
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| LineExplanation {
+                line_number: idx + 1,
+                line: line.to_string(),
+                part_one: part_one.first_and_last(line),
+                part_two: part_two.first_and_last(line),
+            })
+            .collect()
+    }
+
+    pub fn print_explanation(input_lines: &str, format: OutputFormat) -> Result<(), ExplainError> {
+        let explanations = Day01::explain(input_lines);
+        match format {
+            OutputFormat::Text => {
+                let highlight = std::io::stdout().is_terminal();
+                print!("{}", explanations_to_text(&explanations, highlight))
+            }
+            OutputFormat::Json => println!("{}", explanations_to_json(&explanations)),
+            OutputFormat::Html => return Err(ExplainError::UnsupportedFormat { day: 1, format }),
         }
+        Ok(())
+    }
+}
+
+fn total_calibration(lines: &[String], vocabulary: &Vocabulary, acc: Accumulator) -> u32 {
+    let mut total = 0;
+    for line in lines {
+        let calibration = vocabulary
+            .calibration(line)
+            .unwrap_or_else(|err| panic!("{}", err));
+        total = acc.add(total, calibration);
+    }
+    total
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigitMatch {
+    pub position: usize,
+    pub digit: u32,
+    pub spelled: bool,
+}
+
+impl DigitMatch {
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"digit\":{},\"position\":{},\"spelled\":{}}}",
+            self.digit, self.position, self.spelled
+        )
+    }
+}
 
-        total.to_string()
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CalibrationError {
+    NoDigits(String),
+}
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationError::NoDigits(line) => write!(f, "No digits found in line {:?}", line),
+        }
     }
 }
+impl std::error::Error for CalibrationError {}
 
-fn get_nums(line: &str) -> String {
-    let just_nums = line
-        .chars()
-        .filter(|char| char.is_ascii_digit())
-        .collect::<String>();
-    just_nums
+// Words that count as digits on top of the ascii digits themselves.
+#[derive(Clone, Debug, Default)]
+pub struct Vocabulary {
+    words: Vec<(String, u32)>,
 }
+impl Vocabulary {
+    pub fn digits_only() -> Self {
+        Vocabulary::default()
+    }
+
+    pub fn english() -> Self {
+        Vocabulary::digits_only()
+            .with_word("one", 1)
+            .with_word("two", 2)
+            .with_word("three", 3)
+            .with_word("four", 4)
+            .with_word("five", 5)
+            .with_word("six", 6)
+            .with_word("seven", 7)
+            .with_word("eight", 8)
+            .with_word("nine", 9)
+    }
+
+    pub fn with_word(mut self, word: &str, digit: u32) -> Self {
+        self.words.push((word.to_string(), digit));
+        self
+    }
 
+    // Checks every position in the line, so overlapping words such as
+    // "eightwo" produce both an 8 and a 2.
+    pub fn scan(&self, line: &str) -> Vec<DigitMatch> {
+        line.char_indices()
+            .filter_map(|(position, c)| {
+                if let Some(digit) = c.to_digit(10) {
+                    return Some(DigitMatch { position, digit, spelled: false });
+                }
 
-fn calc_calibration(numbers: &str) -> i32 {
-    let first_digit = numbers.chars().next().unwrap();
-    let last_digit = numbers.chars().last().unwrap();
-    let mut number = first_digit.to_string();
-    number.push(last_digit);
-    number.parse::<i32>().unwrap()
+                self.words
+                    .iter()
+                    .find(|(word, _)| line[position..].starts_with(word.as_str()))
+                    .map(|&(_, digit)| DigitMatch { position, digit, spelled: true })
+            })
+            .collect()
+    }
+
+    pub fn first_and_last(&self, line: &str) -> Result<(DigitMatch, DigitMatch), CalibrationError> {
+        let matches = self.scan(line);
+        match (matches.first(), matches.last()) {
+            (Some(first), Some(last)) => Ok((first.clone(), last.clone())),
+            _ => Err(CalibrationError::NoDigits(line.to_string())),
+        }
+    }
+
+    pub fn calibration(&self, line: &str) -> Result<u32, CalibrationError> {
+        let (first, last) = self.first_and_last(line)?;
+        Ok(first.digit * 10 + last.digit)
+    }
 }
 
-fn replace_num_word(line: &str) -> String {
-    let line = line.replace("one", "o1e");
-    let line = line.replace("two", "t2o");
-    let line = line.replace("three", "t3e");
-    let line = line.replace("four", "f4r");
-    let line = line.replace("five", "f5e");
-    let line = line.replace("six", "s6x");
-    let line = line.replace("seven", "s7n");
-    let line = line.replace("eight", "e8t");
-    line.replace("nine", "n9e")
-}
\ No newline at end of file
+type Calibration = Result<(DigitMatch, DigitMatch), CalibrationError>;
+
+#[derive(Clone, Debug)]
+pub struct LineExplanation {
+    pub line_number: usize,
+    pub line: String,
+    pub part_one: Calibration,
+    pub part_two: Calibration,
+}
+impl LineExplanation {
+    pub fn part_one_value(&self) -> Option<u32> {
+        calibration_value(&self.part_one)
+    }
+
+    pub fn part_two_value(&self) -> Option<u32> {
+        calibration_value(&self.part_two)
+    }
+
+    pub fn differs(&self) -> bool {
+        self.part_one_value() != self.part_two_value()
+    }
+}
+
+fn calibration_value(calibration: &Calibration) -> Option<u32> {
+    calibration
+        .as_ref()
+        .ok()
+        .map(|(first, last)| first.digit * 10 + last.digit)
+}
+
+fn calibration_cell(calibration: &Calibration) -> String {
+    match calibration {
+        Ok((first, last)) => format!(
+            "{}@{:<3} {}@{:<3} = {:>2}",
+            first.digit,
+            first.position,
+            last.digit,
+            last.position,
+            first.digit * 10 + last.digit
+        ),
+        Err(_) => format!("{:<17}", "no digits"),
+    }
+}
+
+fn calibration_json(calibration: &Calibration) -> String {
+    match calibration {
+        Ok((first, last)) => format!(
+            "{{\"first\":{},\"last\":{},\"value\":{}}}",
+            first.to_json(),
+            last.to_json(),
+            first.digit * 10 + last.digit
+        ),
+        Err(err) => format!("{{\"error\":{}}}", json_string(&err.to_string())),
+    }
+}
+
+// Digits are shown as digit@position, lines where the parts disagree are
+// marked with a '*' and, with `highlight`, coloured in as well.
+pub fn explanations_to_text(explanations: &[LineExplanation], highlight: bool) -> String {
+    let mut out = format!(
+        "{:>5} | {:<17} | {:<17} |   | line\n",
+        "#", "part 1", "part 2"
+    );
+    for explanation in explanations {
+        let row = format!(
+            "{:>5} | {} | {} | {} | {}",
+            explanation.line_number,
+            calibration_cell(&explanation.part_one),
+            calibration_cell(&explanation.part_two),
+            if explanation.differs() { '*' } else { ' ' },
+            explanation.line
+        );
+        if highlight && explanation.differs() {
+            out.push_str(&format!("{}{}{}\n", HIGHLIGHT, row, RESET));
+        } else {
+            out.push_str(&row);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+pub fn explanations_to_json(explanations: &[LineExplanation]) -> String {
+    let rows = explanations
+        .iter()
+        .map(|explanation| {
+            format!(
+                "{{\"line_number\":{},\"line\":{},\"part_one\":{},\"part_two\":{},\"differs\":{}}}",
+                explanation.line_number,
+                json_string(&explanation.line),
+                calibration_json(&explanation.part_one),
+                calibration_json(&explanation.part_two),
+                explanation.differs()
+            )
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", rows.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day01::{explanations_to_json, explanations_to_text, CalibrationError, Day01, Vocabulary};
+    use crate::report::{HIGHLIGHT, RESET};
+
+    const EXAMPLE: &str = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n4nineeightseven2\nzoneight234\n7pqrstsixteen";
+
+    #[test]
+    fn validate_overlapping_words() {
+        let vocabulary = Vocabulary::english();
+        assert_eq!(vocabulary.calibration("eightwothree"), Ok(83));
+        assert_eq!(vocabulary.calibration("xtwone3fourone"), Ok(21));
+        assert_eq!(vocabulary.calibration("sevenine"), Ok(79));
+    }
+
+    #[test]
+    fn validate_no_digits_is_an_error() {
+        let vocabulary = Vocabulary::digits_only().with_word("zero", 0);
+        assert_eq!(vocabulary.calibration("zeroandzero"), Ok(0));
+        assert_eq!(
+            Vocabulary::digits_only().calibration("eightwothree"),
+            Err(CalibrationError::NoDigits("eightwothree".to_string()))
+        );
+    }
+
+    #[test]
+    fn validate_explanations() {
+        let explanations = Day01::explain(EXAMPLE);
+        let part_two = explanations.iter().map(|line| line.part_two_value().unwrap()).sum::<u32>();
+        assert_eq!(part_two, 281);
+        let differs = explanations.iter().map(|line| line.differs()).collect::<Vec<_>>();
+        assert_eq!(differs, vec![true, true, true, true, false, true, true]);
+
+        let json = explanations_to_json(&explanations[..2]);
+        assert_eq!(
+            json,
+            concat!(
+                "[{\"line_number\":1,\"line\":\"two1nine\",",
+                "\"part_one\":{\"first\":{\"digit\":1,\"position\":3,\"spelled\":false},",
+                "\"last\":{\"digit\":1,\"position\":3,\"spelled\":false},\"value\":11},",
+                "\"part_two\":{\"first\":{\"digit\":2,\"position\":0,\"spelled\":true},",
+                "\"last\":{\"digit\":9,\"position\":4,\"spelled\":true},\"value\":29},\"differs\":true},",
+                "{\"line_number\":2,\"line\":\"eightwothree\",",
+                "\"part_one\":{\"error\":\"No digits found in line \\\"eightwothree\\\"\"},",
+                "\"part_two\":{\"first\":{\"digit\":8,\"position\":0,\"spelled\":true},",
+                "\"last\":{\"digit\":3,\"position\":7,\"spelled\":true},\"value\":83},\"differs\":true}]"
+            )
+        );
+        assert!(explanations_to_json(&explanations).contains("\"line\":\"4nineeightseven2\",\"part_one\":{\"first\":{\"digit\":4"));
+
+        let plain = explanations_to_text(&explanations, false);
+        assert!(!plain.contains(HIGHLIGHT));
+        let lines = plain.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[2].contains("no digits") && lines[2].contains(" * | eightwothree"));
+        assert!(lines[5].ends_with("   | 4nineeightseven2"));
+
+        let highlighted = explanations_to_text(&explanations, true);
+        let lines = highlighted.lines().collect::<Vec<_>>();
+        assert!(lines[1].starts_with(HIGHLIGHT) && lines[1].ends_with(RESET));
+        assert!(!lines[5].starts_with(HIGHLIGHT));
+    }
+}
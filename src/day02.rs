@@ -1,126 +1,556 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    rc::Rc,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, i32 as count, line_ending, one_of, space0, space1},
+    sequence::{delimited, separated_pair},
+    IResult,
+};
+
+use crate::checked::{Accumulator, AccumulateExt};
 use crate::Solution;
-use regex::Regex;
 
 #[derive(Clone, Debug)]
-pub struct Day02;
+pub struct Day02 {
+    games: Vec<Game>,
+    bag: Cubes,
+    // Every colour seen in any game, sorted
+    colours: Vec<Rc<str>>,
+}
+impl Day02 {
+    pub fn with_bag(self, bag: Cubes) -> Self {
+        Day02 { bag, ..self }
+    }
+
+    pub fn games(&self) -> &[Game] {
+        &self.games
+    }
+
+    pub fn colours(&self) -> &[Rc<str>] {
+        &self.colours
+    }
 
-#[derive(Clone, Debug, Copy)]
-pub struct Cubes {
-    red: i32,
-    blue: i32,
-    green: i32,
+    pub fn try_parse(input_lines: &str) -> Result<Day02, ParseError> {
+        let mut colours = Colours::default();
+        let games = input_lines
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| Game::parse(line, idx + 1, &mut colours))
+            .collect::<Result<Vec<Game>, ParseError>>()?;
+
+        Ok(Day02 {
+            games,
+            bag: Cubes::default_bag(),
+            colours: colours.into_sorted(),
+        })
+    }
 }
-impl Cubes {
-    fn new(line: &str) -> Cubes {
-        let mut cubes = Cubes {
-            red: 0,
-            blue: 0,
-            green: 0,
+
+// Where and why the input could not be parsed, line and column start at 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub expected: &'static str,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}",
+            self.line, self.column, self.expected
+        )
+    }
+}
+impl std::error::Error for ParseError {}
+
+// Runs a nom parser over part of `source`, turning a failure into a
+// ParseError positioned from where `source` starts (at `first_line`).
+fn expect<'a, O>(
+    result: IResult<&'a str, O>,
+    source: &str,
+    first_line: usize,
+    expected: &'static str,
+) -> Result<(&'a str, O), ParseError> {
+    result.map_err(|err| {
+        let remaining = match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.input.len(),
+            nom::Err::Incomplete(_) => 0,
         };
-        let re = Regex::new(r"(?<count>[0-9]+) (?<colour>[a-zA-Z]+)").unwrap();
-
-        for cap in re.captures_iter(line) {
-            let colour = cap.name("colour").unwrap().as_str();
-            let count = cap.name("count").unwrap().as_str().parse::<i32>().unwrap();
-            match colour {
-                "red" => cubes.red = count,
-                "green" => cubes.green = count,
-                "blue" => cubes.blue = count,
-                _ => panic!("Unexpected colour {}", colour),
+        let consumed = &source[..source.len() - remaining];
+        let line_start = consumed.rfind('\n').map_or(0, |idx| idx + 1);
+        ParseError {
+            line: first_line + consumed.matches('\n').count(),
+            column: consumed.len() - line_start + 1,
+            expected,
+        }
+    })
+}
+
+fn draw(input: &str) -> IResult<&str, (i32, &str)> {
+    separated_pair(count, space1, alpha1)(input)
+}
+
+// Colour names are shared between every round so parsing a round
+// doesn't allocate a new string per colour.
+#[derive(Debug, Default)]
+struct Colours(HashSet<Rc<str>>);
+impl Colours {
+    fn intern(&mut self, colour: &str) -> Rc<str> {
+        if let Some(interned) = self.0.get(colour) {
+            return interned.clone();
+        }
+        let interned: Rc<str> = Rc::from(colour);
+        self.0.insert(interned.clone());
+        interned
+    }
+
+    fn into_sorted(self) -> Vec<Rc<str>> {
+        let mut colours = self.0.into_iter().collect::<Vec<Rc<str>>>();
+        colours.sort();
+        colours
+    }
+}
+
+// Count of cubes per colour, colours that were never seen count as 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cubes(BTreeMap<Rc<str>, i32>);
+impl Cubes {
+    pub fn new(line: &str) -> Cubes {
+        Cubes::parse(line).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    // Accepts "12 red, 13 green" with entries separated by commas or new lines.
+    pub fn parse(input: &str) -> Result<Cubes, ParseError> {
+        let mut cubes = Cubes::default();
+        let mut rest = input.trim_start();
+
+        while !rest.trim_end().is_empty() {
+            let (next, (count, colour)) = expect(draw(rest), input, 1, "'<count> <colour>'")?;
+            cubes.0.insert(Rc::from(colour), count);
+
+            if next.trim_end().is_empty() {
+                break;
             }
+            let mut separator = delimited(space0, alt((tag(","), line_ending)), space0);
+            let (next, _) = expect(separator(next), input, 1, "',' or a new line")?;
+            rest = next.trim_start();
         }
 
-        cubes
+        Ok(cubes)
+    }
+
+    // The bag from the part one puzzle text.
+    pub fn default_bag() -> Cubes {
+        Cubes::new("12 red, 13 green, 14 blue")
+    }
+
+    pub fn total(&self) -> i32 {
+        self.0.values().sum()
+    }
+
+    pub fn count(&self, colour: &str) -> i32 {
+        self.0.get(colour).copied().unwrap_or(0)
+    }
+
+    pub fn colours(&self) -> impl Iterator<Item = (&str, i32)> {
+        self.0.iter().map(|(colour, &count)| (colour.as_ref(), count))
+    }
+
+    // First colour that has more cubes than the bag allows.
+    pub fn exceeding_colour(&self, cubes_to_verify: &Cubes) -> Option<(&str, i32)> {
+        self.colours()
+            .find(|&(colour, count)| count > cubes_to_verify.count(colour))
     }
 
     pub fn are_cubes_valid_for(&self, cubes_to_verify: &Cubes) -> bool {
-        self.red <= cubes_to_verify.red &&
-        self.blue <= cubes_to_verify.blue &&
-        self.green <= cubes_to_verify.green
+        self.exceeding_colour(cubes_to_verify).is_none()
     }
 
     pub fn combine_with_least_cubes(&self, least_cubes: &mut Cubes) {
-        if self.red > least_cubes.red {
-            least_cubes.red = self.red
+        for (colour, &count) in self.0.iter() {
+            least_cubes.raise(colour, count);
         }
-        if self.green > least_cubes.green {
-            least_cubes.green = self.green
-        }
-        if self.blue > least_cubes.blue {
-            least_cubes.blue = self.blue
+    }
+
+    fn raise(&mut self, colour: &Rc<str>, count: i32) {
+        if count > self.count(colour) {
+            self.0.insert(colour.clone(), count);
         }
-    } 
+    }
+
+    // Product over `colours`, every colour seen in the games, so a colour
+    // the set is missing makes the power 0.
+    pub fn power(&self, colours: &[Rc<str>]) -> i32 {
+        colours.iter().map(|colour| self.count(colour)).product()
+    }
 
-    pub fn power(&self) -> i32 {
-        self.red * self.blue * self.green
+    pub fn checked_power(&self, colours: &[Rc<str>], acc: &Accumulator) -> i64 {
+        colours
+            .iter()
+            .map(|colour| i64::from(self.count(colour)))
+            .product_with(acc)
     }
 
 }
 
+// Why a game could not have been played with a given bag.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub round: usize,
+    pub colour: String,
+    pub count: i32,
+    pub limit: i32,
+}
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "round {} shows {} {} but the bag only holds {}",
+            self.round + 1, self.count, self.colour, self.limit
+        )
+    }
+}
+
+// A single "<count> <colour>" shown during a round.
+#[derive(Clone, Debug)]
+struct Draw {
+    round: usize,
+    colour: Rc<str>,
+    count: i32,
+}
+
+// Draws from every round are kept in one list, in the order they appear.
 #[derive(Clone, Debug)]
 pub struct Game {
     id: i32,
-    rounds: Vec<Cubes>
+    draws: Vec<Draw>
 }
 impl Game {
-    fn new(line: &str) -> Game {
-        let re = Regex::new(r"Game (?<id>[0-9]+): (?<rounds>.+)").unwrap();
-        let Some(caps) = re.captures(line) else {
-            panic!("There was no game")
-        };
+    fn parse(line: &str, line_number: usize, colours: &mut Colours) -> Result<Game, ParseError> {
+        let mut header = delimited(tag("Game "), count, tag(": "));
+        let (mut rest, id) = expect(header(line), line, line_number, "'Game <id>: '")?;
 
-        let id = caps["id"].parse::<i32>().unwrap();
-        let input_rounds = &caps["rounds"];
-        let rounds = input_rounds
-                    .split("; ")
-                    .map(Cubes::new)
-                    .collect::<Vec<Cubes>>();
+        let mut draws = vec![];
+        let mut round = 0;
+        loop {
+            let (next, (count, colour)) = expect(draw(rest), line, line_number, "'<count> <colour>'")?;
+            draws.push(Draw {
+                round,
+                colour: colours.intern(colour),
+                count,
+            });
+
+            if next.is_empty() {
+                break;
+            }
+            let (next, separator) = expect(one_of(",;")(next), line, line_number, "',' or ';'")?;
+            let (next, _) = expect(space1(next), line, line_number, "a space")?;
+            if separator == ';' {
+                round += 1;
+            }
+            rest = next;
+        }
+
+        Ok(Game { id, draws })
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
 
-        Game { id, rounds }
+    pub fn find_violation(&self, cubes_to_verify: &Cubes) -> Option<Violation> {
+        self.draws
+            .iter()
+            .find(|draw| draw.count > cubes_to_verify.count(&draw.colour))
+            .map(|draw| Violation {
+                round: draw.round,
+                colour: draw.colour.to_string(),
+                count: draw.count,
+                limit: cubes_to_verify.count(&draw.colour),
+            })
     }
 
     pub fn are_cubes_valid_for(&self, cubes_to_verify: &Cubes) -> bool {
-        self.rounds.iter().all(|&c| c.are_cubes_valid_for(cubes_to_verify))
+        self.draws
+            .iter()
+            .all(|draw| draw.count <= cubes_to_verify.count(&draw.colour))
     }
 
     pub fn get_least_cubes_combination(&self) -> Cubes {
-        let mut least_cubes = Cubes::new("");
-        self.rounds.iter().for_each(|c| c.combine_with_least_cubes(&mut least_cubes));
+        let mut least_cubes = Cubes::default();
+        self.draws.iter().for_each(|draw| least_cubes.raise(&draw.colour, draw.count));
         least_cubes
     }
 
-    pub fn least_cubes_power(&self) -> i32 {
-        self.get_least_cubes_combination().power()
+    pub fn least_cubes_power(&self, colours: &[Rc<str>]) -> i32 {
+        self.get_least_cubes_combination().power(colours)
+    }
+}
+
+// Questions about which bags would make which games possible. A game is
+// possible with any bag holding at least its least cubes combination, so only
+// the counts from those combinations need to be tried for each colour.
+#[derive(Debug)]
+pub struct BagQueries<'a> {
+    games: &'a [Game],
+    least: Vec<Cubes>,
+    colours: Vec<Rc<str>>,
+}
+impl<'a> BagQueries<'a> {
+    pub fn new(games: &'a [Game]) -> Self {
+        let least = games
+            .iter()
+            .map(|game| game.get_least_cubes_combination())
+            .collect::<Vec<Cubes>>();
+        let mut colours = least
+            .iter()
+            .flat_map(|cubes| cubes.0.keys().cloned())
+            .collect::<Vec<Rc<str>>>();
+        colours.sort();
+        colours.dedup();
+
+        BagQueries { games, least, colours }
+    }
+
+    // Counts a game needs for a colour, plus 0, smallest first.
+    fn candidates(&self, colour: &str) -> Vec<i32> {
+        let mut counts = self.least.iter().map(|cubes| cubes.count(colour)).collect::<Vec<i32>>();
+        counts.push(0);
+        counts.sort();
+        counts.dedup();
+        counts
+    }
+
+    // Calls `f` with every bag built from the candidate counts of `colours`.
+    fn for_each_bag(&self, colours: &[Rc<str>], bag: &mut Cubes, f: &mut dyn FnMut(&Cubes)) {
+        match colours.split_first() {
+            None => f(bag),
+            Some((colour, rest)) => {
+                for count in self.candidates(colour) {
+                    bag.0.insert(colour.clone(), count);
+                    self.for_each_bag(rest, bag, f);
+                }
+                bag.0.remove(colour);
+            }
+        }
+    }
+
+    // Games whose least combination fits in the bag, ignoring the colours it doesn't mention.
+    fn fitting(&self, bag: &'a Cubes) -> impl Iterator<Item = (&'a Game, &Cubes)> + '_ {
+        self.games
+            .iter()
+            .zip(self.least.iter())
+            .filter(move |(_, least)| {
+                bag.colours().all(|(colour, count)| least.count(colour) <= count)
+            })
+    }
+
+    // Smallest bag (by total cubes) that makes at least `k` games possible. Every colour
+    // but the last is tried exhaustively, the last one takes the k-th smallest count
+    // still needed, so this is O(n^colours log n).
+    pub fn smallest_bag_for(&self, k: usize) -> Option<Cubes> {
+        if k > self.games.len() {
+            return None;
+        }
+        let Some((last, rest)) = self.colours.split_last().filter(|_| k > 0) else {
+            return Some(Cubes::default());
+        };
+
+        let mut best: Option<Cubes> = None;
+        self.for_each_bag(rest, &mut Cubes::default(), &mut |partial| {
+            let mut needed = self
+                .fitting(partial)
+                .map(|(_, least)| least.count(last))
+                .collect::<Vec<i32>>();
+            if needed.len() < k {
+                return;
+            }
+            needed.sort();
+
+            let mut bag = partial.clone();
+            bag.0.insert(last.clone(), needed[k - 1]);
+            if best.as_ref().is_none_or(|best| bag.total() < best.total()) {
+                best = Some(bag);
+            }
+        });
+
+        best
+    }
+
+    // Bag holding exactly `total` cubes that maximises the sum of the possible game ids.
+    // Any cubes left over after the other colours are picked go to the last colour.
+    pub fn best_bag_of_size(&self, total: i32) -> Option<(Cubes, i32)> {
+        let Some((last, rest)) = self.colours.split_last() else {
+            let ids = self.games.iter().map(Game::id).sum();
+            return Some((Cubes::default(), ids));
+        };
+
+        let mut best: Option<(Cubes, i32)> = None;
+        self.for_each_bag(rest, &mut Cubes::default(), &mut |partial| {
+            let spare = total - partial.total();
+            if spare < 0 {
+                return;
+            }
+
+            let mut bag = partial.clone();
+            bag.0.insert(last.clone(), spare);
+            let ids = self
+                .games
+                .iter()
+                .filter(|game| game.are_cubes_valid_for(&bag))
+                .map(Game::id)
+                .sum::<i32>();
+            if best.as_ref().is_none_or(|(_, best_ids)| ids > *best_ids) {
+                best = Some((bag, ids));
+            }
+        });
+
+        best
+    }
+
+    // Every bag of exactly `total` cubes, over the known colours, that makes `game` possible.
+    pub fn feasible_bags_of_size(&self, game: &Game, total: i32) -> Vec<Cubes> {
+        let least = game.get_least_cubes_combination();
+        let spare = total - least.total();
+        if spare < 0 || self.colours.is_empty() {
+            return vec![];
+        }
+
+        let mut bags = vec![];
+        let mut bag = Cubes::default();
+        distribute(&self.colours, spare, &least, &mut bag, &mut bags);
+        bags.into_iter()
+            .filter(|bag| game.are_cubes_valid_for(bag))
+            .collect()
+    }
+}
+
+// Shares `spare` cubes between the colours on top of the `least` counts.
+fn distribute(colours: &[Rc<str>], spare: i32, least: &Cubes, bag: &mut Cubes, bags: &mut Vec<Cubes>) {
+    let (colour, rest) = colours.split_first().unwrap();
+    if rest.is_empty() {
+        bag.0.insert(colour.clone(), least.count(colour) + spare);
+        bags.push(bag.clone());
+        return;
+    }
+
+    for extra in 0..=spare {
+        bag.0.insert(colour.clone(), least.count(colour) + extra);
+        distribute(rest, spare - extra, least, bag, bags);
     }
 }
 
 impl Solution for Day02 {
-    type ParsedInput = Vec<Game>;
+    type ParsedInput = Self;
 
     fn parse_input(input_lines: &str) -> Self::ParsedInput {
-        let input_lines = input_lines.to_string();
-        input_lines.lines().map(Game::new).collect::<Vec<Game>>()
+        Day02::try_parse(input_lines).unwrap_or_else(|err| panic!("{}", err))
     }
 
-    
+
     fn part_one(parsed_input: &mut Self::ParsedInput) -> String {
-        let verify_cubes = Cubes {
-            red: 12,
-            green: 13,
-            blue: 14,
-        };
+        let verify_cubes = &parsed_input.bag;
 
-        parsed_input.iter()
-            .filter_map(|game| if game.are_cubes_valid_for(&verify_cubes) { Some(game.id) } else { None })
-            .sum::<i32>()
+        parsed_input.games.iter()
+            .filter_map(|game| if game.are_cubes_valid_for(verify_cubes) { Some(game.id) } else { None })
+            .sum_with(&Accumulator::new(2, 1))
             .to_string()
     }
 
     fn part_two(parsed_input: &mut Self::ParsedInput) -> String {
-        let mut sum = 0;
-        for game in parsed_input {
-            sum += game.least_cubes_power();
+        let acc = Accumulator::new(2, 2);
+        let mut sum: i64 = 0;
+        for game in parsed_input.games.iter() {
+            sum = acc.add(sum, game.get_least_cubes_combination().checked_power(&parsed_input.colours, &acc));
         }
         sum.to_string()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day02::{BagQueries, Cubes, Day02, ParseError, Violation};
+    use crate::Solution;
+
+    const EXAMPLE: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    #[test]
+    fn validate_parse_error_position() {
+        let input = "Game 1: 3 blue, 4 red\nGame 2: 1 blue; two green";
+        let err = Day02::try_parse(input).unwrap_err();
+        assert_eq!(
+            err,
+            ParseError {
+                line: 2,
+                column: 17,
+                expected: "'<count> <colour>'",
+            }
+        );
+
+        let err = Cubes::parse("12 red\n13 green;14 blue").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 9));
+    }
+
+    #[test]
+    fn validate_bag_queries() {
+        let day = Day02::try_parse(EXAMPLE).unwrap();
+        let queries = BagQueries::new(day.games());
+
+        assert_eq!(queries.smallest_bag_for(1).unwrap().total(), 8);
+        assert_eq!(queries.smallest_bag_for(2).unwrap().total(), 13);
+        assert_eq!(queries.smallest_bag_for(5).unwrap(), Cubes::new("20 red, 13 green, 15 blue"));
+        assert_eq!(queries.smallest_bag_for(6), None);
+
+        let (bag, ids) = queries.best_bag_of_size(13).unwrap();
+        assert_eq!((bag.total(), ids), (13, 7));
+
+        let game = &day.games()[1];
+        let bags = queries.feasible_bags_of_size(game, 9);
+        assert_eq!(bags.len(), 3);
+        assert!(bags.iter().all(|bag| game.are_cubes_valid_for(bag) && bag.total() == 9));
+    }
+
+    #[test]
+    fn validate_power_counts_missing_colours() {
+        let input = "Game 1: 3 blue, 4 red; 1 red, 6 blue\nGame 2: 1 blue, 2 green; 3 green, 4 blue, 1 red";
+        let games = Day02::parse_input(input);
+        assert_eq!(games.games()[0].least_cubes_power(games.colours()), 0);
+        assert_eq!(games.games()[1].least_cubes_power(games.colours()), 12);
+        assert_eq!(Day02::part_two(&mut Day02::parse_input(input)), "12");
+    }
+
+    #[test]
+    fn validate_power_ignores_the_bag() {
+        for bag in ["12 red, 13 green", "12 red, 13 green, 14 blue, 1 purple"] {
+            let mut input = Day02::parse_input(EXAMPLE).with_bag(Cubes::new(bag));
+            assert_eq!(Day02::part_two(&mut input), "2286");
+        }
+    }
+
+    #[test]
+    fn validate_violation_names_the_colour() {
+        let day = Day02::parse_input(EXAMPLE);
+        let bag = Cubes::default_bag();
+        assert_eq!(day.games()[0].find_violation(&bag), None);
+
+        let violation = day.games()[2].find_violation(&bag).unwrap();
+        assert_eq!(
+            violation,
+            Violation { round: 0, colour: "red".to_string(), count: 20, limit: 12 }
+        );
+        assert_eq!(violation.to_string(), "round 1 shows 20 red but the bag only holds 12");
+
+        let violation = day.games()[0].find_violation(&Cubes::new("4 red, 6 blue")).unwrap();
+        assert_eq!((violation.round, violation.colour.as_str(), violation.limit), (1, "green", 0));
+    }
+}
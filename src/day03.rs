@@ -1,5 +1,5 @@
-use std::collections::HashSet;
-
+use crate::checked::{Accumulator, AccumulateExt};
+use crate::report::{html_escape, ExplainError, OutputFormat, DIM, GREEN, HIGHLIGHT, RED, RESET};
 use crate::Solution;
 
 #[derive(Clone, Debug, PartialEq, Hash, Eq)]
@@ -9,185 +9,440 @@ pub struct Coord {
 }
 impl Coord {
     pub fn get_neighbours(&self) -> Vec<Coord> {
-        let directions = vec![-1, 0, 1];
+        self.get_neighbours_for(Adjacency::Eight)
+    }
+
+    pub fn get_neighbours_for(&self, adjacency: Adjacency) -> Vec<Coord> {
+        let directions = [-1, 0, 1];
         directions
             .iter()
             .flat_map(|&dx| directions.iter().map(move |&dy| (dx, dy)))
             .filter(|&(dx, dy)| dx != 0 || dy != 0)
+            .filter(|&(dx, dy)| adjacency == Adjacency::Eight || dx == 0 || dy == 0)
             .map(|(dx, dy)| Coord { x: self.x + dx, y: self.y + dy })
             .collect()
     }
+}
 
-    pub fn neighbours_hash_set(&self) -> HashSet<Coord> {
-        HashSet::from_iter(self.get_neighbours())
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Adjacency {
+    // Diagonals count as touching
+    #[default]
+    Eight,
+    // Only up, down, left and right
+    Four,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SymbolSet {
+    // Anything that isn't a digit or '.'
+    #[default]
+    AnyNonDigit,
+    Only(Vec<char>),
+}
+impl SymbolSet {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            SymbolSet::AnyNonDigit => !c.is_ascii_digit() && c != '.',
+            SymbolSet::Only(symbols) => symbols.contains(&c),
+        }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combine {
+    Product,
+    Sum,
+    Max,
+}
+impl Combine {
+    fn apply(&self, values: impl Iterator<Item = isize>, acc: &Accumulator) -> isize {
+        match self {
+            Combine::Product => values.product_with(acc),
+            Combine::Sum => values.sum_with(acc),
+            Combine::Max => values.max().unwrap_or_default(),
+        }
+    }
+}
+
+// A gear is any symbol from `symbols` touching exactly `neighbours` numbers,
+// its ratio is those numbers put together with `combine`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GearRule {
+    pub symbols: SymbolSet,
+    pub neighbours: usize,
+    pub combine: Combine,
+}
+impl Default for GearRule {
+    fn default() -> Self {
+        GearRule {
+            symbols: SymbolSet::Only(vec!['*']),
+            neighbours: 2,
+            combine: Combine::Product,
+        }
+    }
+}
+
+// Defaults to the rules from the puzzle text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rules {
+    pub symbols: SymbolSet,
+    pub adjacency: Adjacency,
+    pub gear: GearRule,
+}
+
+// The schematic along with every number found in it. `index` maps each cell
+// to the id (position in `numbers`) of the number covering it, so lookups
+// around a cell don't need to search through the numbers.
 #[derive(Clone, Debug)]
-pub struct Matrix(Vec<Vec<char>>);
+pub struct Matrix {
+    grid: Vec<Vec<char>>,
+    numbers: Vec<Number>,
+    index: Vec<Vec<Option<usize>>>,
+    rules: Rules,
+}
 impl Matrix {
+    pub fn new(grid: Vec<Vec<char>>) -> Matrix {
+        Matrix::with_rules(grid, Rules::default())
+    }
+
+    pub fn with_rules(grid: Vec<Vec<char>>, rules: Rules) -> Matrix {
+        let mut matrix = Matrix {
+            index: grid.iter().map(|row| vec![None; row.len()]).collect(),
+            grid,
+            numbers: vec![],
+            rules,
+        };
+
+        for y in 0..matrix.max_y() {
+            for x in 0..matrix.grid[y].len() {
+                if matrix.is_start_of_number(x, y) {
+                    let number = matrix.read_number(x, y);
+                    for coord in number.coords() {
+                        matrix.index[coord.y as usize][coord.x as usize] = Some(number.id);
+                    }
+                    matrix.numbers.push(number);
+                }
+            }
+        }
+
+        matrix
+    }
+
     fn get(&self) -> &Vec<Vec<char>> {
-        &self.0
+        &self.grid
     }
 
     fn max_y(&self) -> usize {
         self.get().len()
     }
 
-    fn max_x(&self) -> usize {
-        self.get()[0].len()
-    }
-
     fn is_start_of_number(&self, x: usize, y: usize) -> bool {
         let matrix = self.get();
         matrix[y][x].is_ascii_digit() && ((x == 0) || !matrix[y][x - 1].is_ascii_digit())
     }
 
-    fn is_symbol(&self, coord: &Coord) -> bool {
-        let val = self.get()[coord.y as usize][coord.x as usize];
-        !val.is_ascii_digit() && val != '.'
+    fn cell(&self, coord: &Coord) -> Option<char> {
+        if coord.x < 0 || coord.y < 0 {
+            return None;
+        }
+        self.get()
+            .get(coord.y as usize)
+            .and_then(|row| row.get(coord.x as usize))
+            .copied()
     }
 
-    // PART 1
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
 
-    fn get_numbers(&self) -> Vec<Number> {
-        self.get()
-            .iter()
-            .enumerate()
-            .flat_map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .filter(move |number| self.is_start_of_number(number.0, y))
-                    .filter_map(move |(x, _)| self.get_number(x, y))
-            })
-            .collect::<Vec<Number>>()
+    fn is_symbol(&self, coord: &Coord) -> bool {
+        self.cell(coord)
+            .is_some_and(|val| self.rules.symbols.contains(val))
     }
 
-    fn get_number(&self, x: usize, y: usize) -> Option<Number> {
-        let matrix = self.get();
-        let current = matrix[y][x];
-        if !current.is_ascii_digit() {
-            return None;
-        }
+    fn read_number(&self, x: usize, y: usize) -> Number {
+        let row = &self.get()[y];
+        let end = (x..row.len())
+            .find(|&next_x| !row[next_x].is_ascii_digit())
+            .unwrap_or(row.len());
 
-        let mut number_value = String::from(current);
-        let mut coords = HashSet::new();
-        coords.insert(Coord {
-            x: x as isize,
+        Number {
+            id: self.numbers.len(),
+            value: row[x..end].iter().collect::<String>().parse().unwrap_or_default(),
             y: y as isize,
-        });
-
-        let mut next_x = x + 1;
-        while let Some(cell) = matrix[y].get(next_x) {
-            if !cell.is_ascii_digit() {
-                break;
-            }
+            x_start: x as isize,
+            x_end: end as isize,
+        }
+    }
 
-            coords.insert(Coord {
-                x: next_x as isize,
-                y: y as isize,
-            });
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
 
-            number_value.push(*cell);
-            next_x += 1;
+    pub fn number_at(&self, coord: &Coord) -> Option<&Number> {
+        if coord.x < 0 || coord.y < 0 {
+            return None;
         }
+        self.index
+            .get(coord.y as usize)
+            .and_then(|row| row.get(coord.x as usize))
+            .copied()
+            .flatten()
+            .map(|id| &self.numbers[id])
+    }
 
-        Some(Number {
-            value: number_value.parse().unwrap_or_default(),
-            coords,
+    pub fn symbols(&self) -> impl Iterator<Item = (Coord, char)> + '_ {
+        self.get().iter().enumerate().flat_map(move |(y, row)| {
+            row.iter().enumerate().filter_map(move |(x, &c)| {
+                let coord = Coord { x: x as isize, y: y as isize };
+                self.is_symbol(&coord).then_some((coord, c))
+            })
         })
     }
 
-    fn get_neighboring_numbers(&self, number: &Number) -> HashSet<Coord> {
-        let mut neighbors = HashSet::new();
+    // Each number is only returned once even if it touches the cell more than once.
+    pub fn numbers_adjacent_to(&self, coord: &Coord) -> Vec<&Number> {
+        let mut numbers = coord
+            .get_neighbours_for(self.rules.adjacency)
+            .iter()
+            .filter_map(|neighbour| self.number_at(neighbour))
+            .collect::<Vec<&Number>>();
+        numbers.sort_by_key(|number| number.id);
+        numbers.dedup_by_key(|number| number.id);
+        numbers
+    }
 
-        for coord in number.coords.iter() {
-            let coord_neighbors = self
-                .get_neighboring_points(coord)
-                .into_iter()
-                .filter(|point| (!number.coords.contains(point)))
-                .filter(|point| (!neighbors.contains(point)))
-                .collect::<HashSet<Coord>>();
+    pub fn symbols_adjacent_to(&self, number: &Number) -> Vec<(Coord, char)> {
+        number
+            .border(self.rules.adjacency)
+            .filter_map(|coord| self.cell(&coord).map(|c| (coord, c)))
+            .filter(|(coord, _)| self.is_symbol(coord))
+            .collect()
+    }
 
-            neighbors.extend(coord_neighbors);
-        }
-        neighbors
+    // Symbols of the given type that touch exactly `count` numbers.
+    pub fn symbols_with_neighbours(&self, symbol: char, count: usize) -> Vec<(Coord, Vec<&Number>)> {
+        self.symbols()
+            .filter(|&(_, c)| c == symbol)
+            .map(|(coord, _)| {
+                let numbers = self.numbers_adjacent_to(&coord);
+                (coord, numbers)
+            })
+            .filter(|(_, numbers)| numbers.len() == count)
+            .collect()
     }
 
-    pub fn get_part_numbers(&self) -> Vec<Number> {
-        let mut part_numbers = Vec::new();
+    // PART 1
 
-        for number in self.get_numbers() {
-            let neighbors = self.get_neighboring_numbers(&number);
-            if neighbors.iter().any(|neighbor| self.is_symbol(neighbor)) {
-                part_numbers.push(number);
-            }
-        }
+    pub fn get_part_numbers(&self) -> Vec<&Number> {
+        self.numbers
+            .iter()
+            .filter(|number| !self.symbols_adjacent_to(number).is_empty())
+            .collect()
+    }
+
+    // PART 2
 
-        part_numbers
+    pub fn get_gears(&self) -> Vec<(Coord, Vec<&Number>)> {
+        let gear = &self.rules.gear;
+        self.symbols()
+            .filter(|&(_, c)| gear.symbols.contains(c))
+            .map(|(coord, _)| {
+                let numbers = self.numbers_adjacent_to(&coord);
+                (coord, numbers)
+            })
+            .filter(|(_, numbers)| numbers.len() == gear.neighbours)
+            .collect()
     }
 
-    fn get_neighboring_points(&self, coord: &Coord) -> HashSet<Coord> {
-        coord
-            .neighbours_hash_set()
+    fn get_gear_ratios(&self, acc: &Accumulator) -> Vec<isize> {
+        self.get_gears()
             .into_iter()
-            .filter(|coord| self.does_coord_exist(coord))
-            .collect::<HashSet<Coord>>()
+            .map(|(_, numbers)| {
+                self.rules.gear.combine.apply(numbers.iter().map(|number| number.value), acc)
+            })
+            .collect()
     }
 
-    fn does_coord_exist(&self, coord: &Coord) -> bool {
-        (coord.x >= 0)
-            && (coord.x < self.max_x() as isize)
-            && (coord.y >= 0)
-            && (coord.y < self.max_y() as isize)
-    }
+}
 
-    // PART 2
-    fn get_surrounding_numbers<'a>(&self, coord: &Coord, numbers: &'a[Number]) -> Vec<&'a Number>{
-        let neighbors: HashSet<Coord> = self.get_neighboring_points(coord);
-        numbers.iter()
-                .filter(|&part_number| !part_number.coords.is_disjoint(&neighbors))
-                .collect()
-    }
-
-    fn get_possible_gears(&self) -> Vec<Coord> {
-        (0..self.max_y())
-            .flat_map(|y| 
-                (0..self.max_x())
-                    .filter(move |&x| self.0[y][x] == '*')
-                    .map(move |x| Coord { x: x as isize, y: y as isize }))
-            .collect()
+// How a cell is drawn when rendering the schematic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Style {
+    Plain,
+    PartNumber,
+    OtherNumber,
+    Gear,
+    UnusedSymbol,
+}
+impl Style {
+    fn ansi(&self) -> &'static str {
+        match self {
+            Style::Plain => "",
+            Style::PartNumber => GREEN,
+            Style::OtherNumber => RED,
+            Style::Gear => HIGHLIGHT,
+            Style::UnusedSymbol => DIM,
+        }
     }
 
-    fn get_gear_ratios(&self) -> Vec<isize> {
-        let mut gear_ratios = vec![];
+    fn html_class(&self) -> &'static str {
+        match self {
+            Style::Plain => "",
+            Style::PartNumber => "part",
+            Style::OtherNumber => "other",
+            Style::Gear => "gear",
+            Style::UnusedSymbol => "unused",
+        }
+    }
+}
 
-        let possible_gears = self.get_possible_gears();
+const HTML_STYLE: &str = ".part { color: green; } .other { color: red; } \
+.gear { background: gold; font-weight: bold; } .unused { opacity: 0.4; }";
 
-        let part_numbers = self.get_part_numbers();
+impl Matrix {
+    fn styles(&self) -> Vec<Vec<Style>> {
+        let mut styles = self
+            .get()
+            .iter()
+            .map(|row| vec![Style::Plain; row.len()])
+            .collect::<Vec<Vec<Style>>>();
 
-        for potential_gear in possible_gears {
-            let matching_part_numbers =
-                self.get_surrounding_numbers(&potential_gear, &part_numbers);
+        let mut is_part = vec![false; self.numbers.len()];
+        for number in self.get_part_numbers() {
+            is_part[number.id] = true;
+        }
+        for number in self.numbers.iter() {
+            let style = match is_part[number.id] {
+                true => Style::PartNumber,
+                false => Style::OtherNumber,
+            };
+            for coord in number.coords() {
+                styles[coord.y as usize][coord.x as usize] = style;
+            }
+        }
 
-            if matching_part_numbers.len() == 2 {
-                gear_ratios.push(matching_part_numbers[0].value * matching_part_numbers[1].value);
+        for (coord, _) in self.symbols() {
+            if self.numbers_adjacent_to(&coord).is_empty() {
+                styles[coord.y as usize][coord.x as usize] = Style::UnusedSymbol;
             }
         }
+        for (coord, _) in self.get_gears() {
+            styles[coord.y as usize][coord.x as usize] = Style::Gear;
+        }
+
+        styles
+    }
+
+    // Runs of cells on a row that share a style.
+    fn styled_runs(&self) -> Vec<Vec<(Style, String)>> {
+        self.get()
+            .iter()
+            .zip(self.styles())
+            .map(|(row, row_styles)| {
+                let mut runs: Vec<(Style, String)> = vec![];
+                for (&c, style) in row.iter().zip(row_styles) {
+                    match runs.last_mut() {
+                        Some((last, text)) if *last == style => text.push(c),
+                        _ => runs.push((style, c.to_string())),
+                    }
+                }
+                runs
+            })
+            .collect()
+    }
+
+    // Part numbers are green, other numbers red, gears highlighted and
+    // symbols that touch no numbers dimmed.
+    pub fn render(&self, format: OutputFormat) -> Result<String, ExplainError> {
+        match format {
+            OutputFormat::Text => Ok(self.render_ansi()),
+            OutputFormat::Html => Ok(self.render_html()),
+            OutputFormat::Json => Err(ExplainError::UnsupportedFormat { day: 3, format }),
+        }
+    }
 
-        gear_ratios
+    fn render_ansi(&self) -> String {
+        let mut out = String::new();
+        for runs in self.styled_runs() {
+            for (style, text) in runs {
+                match style {
+                    Style::Plain => out.push_str(&text),
+                    _ => out.push_str(&format!("{}{}{}", style.ansi(), text, RESET)),
+                }
+            }
+            out.push('\n');
+        }
+        out
     }
 
+    fn render_html(&self) -> String {
+        let mut out = format!("<style>{}</style>\n<pre class=\"schematic\">\n", HTML_STYLE);
+        for runs in self.styled_runs() {
+            for (style, text) in runs {
+                match style {
+                    Style::Plain => out.push_str(&html_escape(&text)),
+                    _ => out.push_str(&format!(
+                        "<span class=\"{}\">{}</span>",
+                        style.html_class(),
+                        html_escape(&text)
+                    )),
+                }
+            }
+            out.push('\n');
+        }
+        out.push_str("</pre>\n");
+        out
+    }
 }
 
+// A number spans the cells x_start..x_end on row y.
 #[derive(Clone, Debug)]
 pub struct Number {
+    id: usize,
     value: isize,
-    coords: HashSet<Coord>,
+    y: isize,
+    x_start: isize,
+    x_end: isize,
+}
+impl Number {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn value(&self) -> isize {
+        self.value
+    }
+
+    pub fn coords(&self) -> impl Iterator<Item = Coord> + '_ {
+        (self.x_start..self.x_end).map(|x| Coord { x, y: self.y })
+    }
+
+    // Cells touching the number, these may be outside the schematic.
+    fn border(&self, adjacency: Adjacency) -> impl Iterator<Item = Coord> + '_ {
+        let (from, to) = match adjacency {
+            Adjacency::Eight => (self.x_start - 1, self.x_end + 1),
+            Adjacency::Four => (self.x_start, self.x_end),
+        };
+        let above_and_below = (from..to)
+            .flat_map(|x| [Coord { x, y: self.y - 1 }, Coord { x, y: self.y + 1 }]);
+        let sides = [
+            Coord { x: self.x_start - 1, y: self.y },
+            Coord { x: self.x_end, y: self.y },
+        ];
+        above_and_below.chain(sides)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
 pub struct Day03;
+impl Day03 {
+    pub fn print_explanation(input_lines: &str, format: OutputFormat) -> Result<(), ExplainError> {
+        print!("{}", Day03::parse_input(input_lines).render(format)?);
+        Ok(())
+    }
+}
 
 impl Solution for Day03 {
     type ParsedInput = Matrix;
@@ -199,7 +454,7 @@ impl Solution for Day03 {
             .map(String::from)
             .map(|x| x.chars().collect::<Vec<char>>())
             .collect::<Vec<Vec<char>>>();
-        Matrix(data)
+        Matrix::new(data)
     }
 
     fn part_one(matrix: &mut Self::ParsedInput) -> String {
@@ -208,12 +463,126 @@ impl Solution for Day03 {
         let sum: isize = part_numbers
             .iter()
             .map(|part_number| part_number.value)
-            .sum();
+            .sum_with(&Accumulator::new(3, 1));
         sum.to_string()
     }
 
     fn part_two(matrix: &mut Self::ParsedInput) -> String {
-        let sum: isize = matrix.get_gear_ratios().iter().sum();
+        let acc = Accumulator::new(3, 2);
+        let sum: isize = matrix.get_gear_ratios(&acc).into_iter().sum_with(&acc);
         sum.to_string()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checked::Accumulator;
+    use crate::day03::{Adjacency, Combine, Coord, Day03, GearRule, Matrix, Number, Rules, SymbolSet};
+    use crate::report::{ExplainError, OutputFormat, DIM, GREEN, HIGHLIGHT, RED, RESET};
+    use crate::Solution;
+
+    const EXAMPLE: &str = "467..114..\n...*......\n..35..633.\n......#...\n617*......\n.....+.58.\n..592.....\n......755.\n...$.*....\n.664.598..";
+
+    fn grid(input: &str) -> Vec<Vec<char>> {
+        input.lines().map(|line| line.chars().collect()).collect()
+    }
+
+    fn values(numbers: &[&Number]) -> Vec<isize> {
+        numbers.iter().map(|number| number.value()).collect()
+    }
+
+    #[test]
+    fn validate_gear_rules() {
+        let input = "12.3\n.#..\n4.5.";
+        let acc = Accumulator::new(3, 2);
+
+        let matrix = Matrix::new(grid(input));
+        assert_eq!(matrix.get_part_numbers().len(), 3);
+        assert!(matrix.get_gear_ratios(&acc).is_empty());
+
+        let rules = Rules {
+            symbols: SymbolSet::Only(vec!['#']),
+            adjacency: Adjacency::Four,
+            gear: GearRule {
+                symbols: SymbolSet::Only(vec!['#']),
+                neighbours: 1,
+                combine: Combine::Sum,
+            },
+        };
+        let matrix = Matrix::with_rules(grid(input), rules);
+        assert_eq!(matrix.get_part_numbers().len(), 1);
+        assert_eq!(matrix.get_gear_ratios(&acc), vec![12]);
+    }
+
+    #[test]
+    fn validate_example_queries() {
+        assert_eq!(Day03::solve(EXAMPLE), ("4361".to_string(), "467835".to_string()));
+
+        let matrix = Matrix::new(grid(EXAMPLE));
+        assert_eq!(matrix.numbers().len(), 10);
+        assert_eq!(matrix.number_at(&Coord { x: 1, y: 0 }).map(Number::value), Some(467));
+        assert_eq!(matrix.number_at(&Coord { x: 3, y: 0 }).map(Number::value), None);
+        assert_eq!(matrix.number_at(&Coord { x: -1, y: 0 }).map(Number::value), None);
+        assert_eq!(matrix.number_at(&Coord { x: 0, y: 10 }).map(Number::value), None);
+
+        assert_eq!(values(&matrix.numbers_adjacent_to(&Coord { x: 3, y: 1 })), vec![467, 35]);
+        assert_eq!(values(&matrix.numbers_adjacent_to(&Coord { x: 0, y: 2 })), vec![]);
+
+        let six_seventeen = matrix.number_at(&Coord { x: 0, y: 4 }).unwrap();
+        assert_eq!(matrix.symbols_adjacent_to(six_seventeen), vec![(Coord { x: 3, y: 4 }, '*')]);
+        let one_fourteen = matrix.number_at(&Coord { x: 5, y: 0 }).unwrap();
+        assert_eq!(matrix.symbols_adjacent_to(one_fourteen), vec![]);
+
+        let gears = matrix
+            .symbols_with_neighbours('*', 2)
+            .into_iter()
+            .map(|(coord, numbers)| (coord, values(&numbers)))
+            .collect::<Vec<_>>();
+        assert_eq!(gears, vec![(Coord { x: 3, y: 1 }, vec![467, 35]), (Coord { x: 5, y: 8 }, vec![755, 598])]);
+        let lonely = matrix.symbols_with_neighbours('*', 1);
+        assert_eq!(lonely.len(), 1);
+        assert_eq!((&lonely[0].0, values(&lonely[0].1)), (&Coord { x: 3, y: 4 }, vec![617]));
+    }
+
+    #[test]
+    fn validate_number_touching_symbol_twice() {
+        // The '#' borders all three digits of 123
+        let matrix = Matrix::new(grid("123\n.#."));
+        let hash = Coord { x: 1, y: 1 };
+        assert_eq!(values(&matrix.numbers_adjacent_to(&hash)), vec![123]);
+        assert_eq!(matrix.symbols_adjacent_to(&matrix.numbers()[0]), vec![(hash.clone(), '#')]);
+        assert_eq!(matrix.symbols_with_neighbours('#', 1).len(), 1);
+        assert!(matrix.symbols_with_neighbours('#', 3).is_empty());
+    }
+
+    #[test]
+    fn validate_render() {
+        // 1 and 2 share a gear, 9 is a part through '&', 7 touches nothing
+        // and '<' is an unused symbol
+        let matrix = Matrix::new(grid("1.2.<\n.*...\n....&\n7..9."));
+
+        let html = matrix.render(OutputFormat::Html).unwrap();
+        let rows = html.lines().skip(2).take(4).collect::<Vec<_>>();
+        assert_eq!(rows, vec![
+            "<span class=\"part\">1</span>.<span class=\"part\">2</span>.<span class=\"unused\">&lt;</span>",
+            ".<span class=\"gear\">*</span>...",
+            "....&amp;",
+            "<span class=\"other\">7</span>..<span class=\"part\">9</span>.",
+        ]);
+        assert!(html.starts_with("<style>"));
+        assert!(html.ends_with("</pre>\n"));
+
+        let ansi = matrix.render(OutputFormat::Text).unwrap();
+        assert_eq!(ansi.lines().collect::<Vec<_>>(), vec![
+            format!("{GREEN}1{RESET}.{GREEN}2{RESET}.{DIM}<{RESET}"),
+            format!(".{HIGHLIGHT}*{RESET}..."),
+            "....&".to_string(),
+            format!("{RED}7{RESET}..{GREEN}9{RESET}."),
+        ]);
+
+        assert_eq!(
+            matrix.render(OutputFormat::Json),
+            Err(ExplainError::UnsupportedFormat { day: 3, format: OutputFormat::Json })
+        );
+    }
+}
@@ -1,14 +1,18 @@
+use std::collections::HashSet;
+
+use crate::checked::{Accumulator, AccumulateExt};
+use crate::report::{ExplainError, OutputFormat};
 use crate::Solution;
 
 #[derive(Clone, Debug)]
 pub struct Card {
     id: usize,
-    winning_numbers: Vec<usize>,
-    matching_numbers: Vec<usize>
+    winning_numbers: HashSet<usize>,
+    matching_numbers: HashSet<usize>
 }
 
 impl Card {
-    fn new(id: usize, winning_numbers: Vec<usize>, matching_numbers: Vec<usize>) -> Self {
+    fn new(id: usize, winning_numbers: HashSet<usize>, matching_numbers: HashSet<usize>) -> Self {
         Card {
             id,
             winning_numbers,
@@ -16,18 +20,65 @@ impl Card {
         }
     }
 
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     fn match_numbers(&self) -> usize {
+        // A number repeated on either side still only matches once
         self.winning_numbers
-            .iter()
-            .filter(|&x| self.matching_numbers.contains(x))
-            .cloned()
-            .collect::<Vec<usize>>()
-            .len()
+            .intersection(&self.matching_numbers)
+            .count()
     }
 }
 
+// How many copies of a card were held once every card was scratched,
+// and which earlier cards (by id) handed out those copies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CascadeEntry {
+    pub id: usize,
+    pub matches: usize,
+    pub copies: usize,
+    pub won_from: Vec<(usize, usize)>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Day04;
+impl Day04 {
+    // Cards only ever win copies of the cards after them, wins that would
+    // go past the last card are dropped.
+    pub fn cascade(cards: &[Card], acc: &Accumulator) -> Vec<CascadeEntry> {
+        let mut cascade = cards
+            .iter()
+            .map(|card| CascadeEntry {
+                id: card.id,
+                matches: card.match_numbers(),
+                copies: 1,
+                won_from: vec![],
+            })
+            .collect::<Vec<CascadeEntry>>();
+
+        for i in 0..cascade.len() {
+            let (source, rest) = cascade[i..].split_first_mut().unwrap();
+            for won in rest.iter_mut().take(source.matches) {
+                won.copies = acc.add(won.copies, source.copies);
+                won.won_from.push((source.id, source.copies));
+            }
+        }
+
+        cascade
+    }
+
+    pub fn print_explanation(input_lines: &str, format: OutputFormat) -> Result<(), ExplainError> {
+        let cascade = Day04::cascade(&Day04::parse_input(input_lines), &Accumulator::new(4, 2));
+        match format {
+            OutputFormat::Text => print!("{}", cascade_to_text(&cascade)),
+            OutputFormat::Json => println!("{}", cascade_to_json(&cascade)),
+            OutputFormat::Html => return Err(ExplainError::UnsupportedFormat { day: 4, format }),
+        }
+        Ok(())
+    }
+}
 
 impl Solution for Day04 {
     type ParsedInput = Vec<Card>;
@@ -35,55 +86,117 @@ impl Solution for Day04 {
     fn parse_input(input_lines: &str) -> Self::ParsedInput {
         let input_lines = input_lines.to_string();
         input_lines.lines()
-            .map(|line| parse_line(&line).unwrap())
+            .map(|line| parse_line(line).unwrap_or_else(|| panic!("Couldn't parse card {:?}", line)))
             .collect()
     }
 
     fn part_one(cards: &mut Self::ParsedInput) -> String {
+        let acc = Accumulator::new(4, 1);
         let sum: i64 = cards.iter()
             .map(|card| card.match_numbers())
             .filter(|&num_matches| num_matches > 0)
-            .map(|num_matches| 2_i64.pow((num_matches as u32 - 1).into()))
-            .sum();
+            .map(|num_matches| acc.pow(2_i64, num_matches as u32 - 1))
+            .sum_with(&acc);
         sum.to_string()
     }
 
     fn part_two(cards: &mut Self::ParsedInput) -> String {
-        let mut copies = vec![1; cards.len()];
-        for (i, card) in cards.iter().enumerate() {
-            let total_matches = card.match_numbers();
-            for j in 1..=total_matches {
-                copies[i + j] += copies[i];
-            }
-        }
-        let sum: usize = copies.iter().sum();
+        let acc = Accumulator::new(4, 2);
+        let sum: usize = Day04::cascade(cards, &acc)
+            .into_iter()
+            .map(|entry| entry.copies)
+            .sum_with(&acc);
         sum.to_string()
     }
 }
 
-fn parse_line(line: &str) ->  Option<Card> {
-    let parts: Vec<&str> = line.split(":").collect();
-    let parts_len = parts.len();
-    match parts_len {
-        2 => {
-            let id = parts[0]
-                .trim_start_matches("Card ")
-                .trim()
-                .parse()
-                .expect("Failed to parse id to a number");
-    
-            let numbers: Vec<usize> = parts[1]
-                .split('|')
-                .flat_map(|part| part.split_whitespace().filter_map(|s| s.parse().ok()))
-                .collect();
-    
-            let split_index = numbers.len().min(10);
-            let (winning_numbers, matching_numbers) = numbers.split_at(split_index);
-    
-            Some(Card::new(id, winning_numbers.to_vec(), matching_numbers.to_vec()))
-        },
-        _ => {
-            panic!("Error with parsing file: expected 2 parts, got {}", parts_len);
-        },
-    }    
-}
\ No newline at end of file
+// "Card 1: 41 48 83 | 83 86  6 31", any amount of numbers on either side of the '|'
+fn parse_line(line: &str) -> Option<Card> {
+    let (card, numbers) = line.split_once(':')?;
+    let id = card.strip_prefix("Card")?.trim().parse().ok()?;
+    let (winning, matching) = numbers.split_once('|')?;
+
+    let winning_numbers = winning
+        .split_whitespace()
+        .map(|s| s.parse().ok())
+        .collect::<Option<HashSet<usize>>>()?;
+    let matching_numbers = matching
+        .split_whitespace()
+        .map(|s| s.parse().ok())
+        .collect::<Option<HashSet<usize>>>()?;
+
+    Some(Card::new(id, winning_numbers, matching_numbers))
+}
+
+fn cascade_to_text(cascade: &[CascadeEntry]) -> String {
+    let mut out = format!("{:>6} | {:>7} | {:>8} | won from (card x copies)\n", "card", "matches", "copies");
+    for entry in cascade {
+        let won_from = entry
+            .won_from
+            .iter()
+            .map(|(id, copies)| format!("{} x{}", id, copies))
+            .collect::<Vec<String>>()
+            .join(", ");
+        out.push_str(&format!(
+            "{:>6} | {:>7} | {:>8} | {}\n",
+            entry.id, entry.matches, entry.copies, won_from
+        ));
+    }
+    out
+}
+
+fn cascade_to_json(cascade: &[CascadeEntry]) -> String {
+    let rows = cascade
+        .iter()
+        .map(|entry| {
+            let won_from = entry
+                .won_from
+                .iter()
+                .map(|(id, copies)| format!("{{\"card\":{},\"copies\":{}}}", id, copies))
+                .collect::<Vec<String>>()
+                .join(",");
+            format!(
+                "{{\"card\":{},\"matches\":{},\"copies\":{},\"won_from\":[{}]}}",
+                entry.id, entry.matches, entry.copies, won_from
+            )
+        })
+        .collect::<Vec<String>>();
+    format!("[{}]", rows.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checked::Accumulator;
+    use crate::day04::Day04;
+    use crate::Solution;
+
+    const EXAMPLE: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    #[test]
+    fn validate_example() {
+        let (p1, p2) = Day04::solve(EXAMPLE);
+        assert_eq!((p1.as_str(), p2.as_str()), ("13", "30"));
+    }
+
+    #[test]
+    fn validate_wins_past_the_last_card_are_dropped() {
+        let cards = Day04::parse_input("Card 1: 1 2 | 1 9\nCard 2: 1 2 3 | 1 2 3");
+        let cascade = Day04::cascade(&cards, &Accumulator::new(4, 2));
+        assert_eq!(cascade.iter().map(|entry| entry.copies).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(cascade[1].won_from, vec![(1, 1)]);
+        assert_eq!(cascade[1].matches, 3);
+    }
+
+    #[test]
+    fn validate_repeated_numbers_match_once() {
+        let cards = Day04::parse_input("Card 1: 5 7 5 | 5 5 7 7 9\nCard 2: 1 | 1");
+        let cascade = Day04::cascade(&cards, &Accumulator::new(4, 2));
+        assert_eq!(cascade[0].matches, 2);
+        assert_eq!(cascade.iter().map(|entry| entry.copies).collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
@@ -1,9 +1,18 @@
-use std::ops::Range;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    ops::Range,
+};
 
+use crate::report::{ExplainError, OutputFormat};
 use crate::Solution;
 
-#[derive(Default, Debug)]
-struct Map {
+// Every value the composed/inverse maps need to consider. Kept well inside
+// i64 so adding a delta to either end can't overflow.
+const DOMAIN: Range<i64> = i64::MIN / 4..i64::MAX / 4;
+
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Map {
     range: Range<i64>,
     delta: i64
 }
@@ -17,10 +26,14 @@ impl Map {
             delta: dest - src
         }
     }
+
+    fn dest_start(&self) -> i64 {
+        self.range.start + self.delta
+    }
 }
 
-#[derive(Default, Debug)]
-struct Mappings {
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Mappings {
     maps: Vec<Map>
 }
 impl Mappings {
@@ -37,51 +50,405 @@ impl Mappings {
 
         val
     }
+
+    // Same as apply_map for every value in the ranges at once. Ranges are split
+    // wherever they cross the edge of a map, the pieces no map covers pass
+    // through unchanged.
+    pub fn apply_ranges(&self, ranges: Vec<Range<i64>>) -> Vec<Range<i64>> {
+        ranges
+            .into_iter()
+            .flat_map(|range| self.pieces(range))
+            .map(|m| m.range.start + m.delta..m.range.end + m.delta)
+            .collect()
+    }
+
+    // Splits the range into the pieces each map (or no map, delta 0) applies to,
+    // sorted by where they start. Earlier maps win where maps overlap, like apply_map.
+    fn pieces(&self, range: Range<i64>) -> Vec<Map> {
+        let mut pieces = vec![];
+        let mut unmapped = vec![range];
+
+        for m in &self.maps {
+            let mut remaining = vec![];
+            for range in unmapped {
+                let overlap = range.start.max(m.range.start)..range.end.min(m.range.end);
+                if overlap.is_empty() {
+                    remaining.push(range);
+                    continue;
+                }
+
+                pieces.push(Map { range: overlap.clone(), delta: m.delta });
+                if range.start < overlap.start {
+                    remaining.push(range.start..overlap.start);
+                }
+                if overlap.end < range.end {
+                    remaining.push(overlap.end..range.end);
+                }
+            }
+            unmapped = remaining;
+        }
+
+        pieces.extend(
+            unmapped
+                .into_iter()
+                .filter(|range| !range.is_empty())
+                .map(|range| Map { range, delta: 0 }),
+        );
+        pieces.sort_by_key(|m| m.range.start);
+        pieces
+    }
+
+    // Problems with the source ranges of the maps, in order of where they start.
+    pub fn issues(&self) -> Vec<IssueKind> {
+        let mut issues = vec![];
+        let mut maps = self.maps.iter().collect::<Vec<&Map>>();
+        maps.sort_by_key(|m| (m.range.start, m.range.end));
+
+        for m in maps.iter().filter(|m| m.range.is_empty()) {
+            issues.push(IssueKind::EmptyRange(m.range.clone()));
+        }
+
+        let maps = maps.into_iter().filter(|m| !m.range.is_empty()).collect::<Vec<&Map>>();
+        for (i, a) in maps.iter().enumerate() {
+            for b in maps[i + 1..].iter().take_while(|b| b.range.start < a.range.end) {
+                issues.push(IssueKind::Overlap(a.range.clone(), b.range.clone()));
+            }
+        }
+
+        let mut covered_to = maps.first().map(|m| m.range.end);
+        for m in maps.iter().skip(1) {
+            let end = covered_to.unwrap();
+            if m.range.start > end {
+                issues.push(IssueKind::Gap(end..m.range.start));
+            }
+            covered_to = Some(end.max(m.range.end));
+        }
+
+        issues
+    }
+
+    // Equivalent mappings with no overlaps, sorted by source, no identity maps
+    // and neighbouring maps with the same delta merged.
+    pub fn normalized(&self) -> Mappings {
+        let mut maps: Vec<Map> = vec![];
+        for piece in self.pieces(DOMAIN).into_iter().filter(|m| m.delta != 0) {
+            match maps.last_mut() {
+                Some(last) if last.range.end == piece.range.start && last.delta == piece.delta => {
+                    last.range.end = piece.range.end;
+                }
+                _ => maps.push(piece),
+            }
+        }
+        Mappings { maps }
+    }
+
+    // Applies self and then next as one normalized map.
+    pub fn then(&self, next: &Mappings) -> Mappings {
+        let mut maps = vec![];
+        for piece in self.pieces(DOMAIN) {
+            let image = piece.range.start + piece.delta..piece.range.end + piece.delta;
+            for next_piece in next.pieces(image) {
+                maps.push(Map {
+                    range: next_piece.range.start - piece.delta..next_piece.range.end - piece.delta,
+                    delta: piece.delta + next_piece.delta,
+                });
+            }
+        }
+        Mappings { maps }.normalized()
+    }
+
+    // Every value that maps onto `val`.
+    pub fn preimage(&self, val: i64) -> Vec<i64> {
+        let mut sources = self
+            .pieces(DOMAIN)
+            .into_iter()
+            .map(|m| val - m.delta)
+            .filter(|&src| DOMAIN.contains(&src) && self.apply_map(src) == val)
+            .collect::<Vec<i64>>();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    // The map going back from destination to source, only possible when no
+    // two values map onto the same destination.
+    pub fn inverse(&self) -> Option<Mappings> {
+        let mut images = self
+            .pieces(DOMAIN)
+            .into_iter()
+            .map(|m| Map { range: m.dest_start()..m.range.end + m.delta, delta: -m.delta })
+            .collect::<Vec<Map>>();
+        images.sort_by_key(|m| m.range.start);
+        if images.windows(2).any(|w| w[0].range.end > w[1].range.start) {
+            return None;
+        }
+
+        Some(Mappings { maps: images }.normalized())
+    }
+
+    // "dest src len" lines, the same layout as the almanac.
+    pub fn to_table(&self) -> String {
+        self.maps
+            .iter()
+            .map(|m| format!("{} {} {}", m.dest_start(), m.range.start, m.range.end - m.range.start))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        let maps = self
+            .maps
+            .iter()
+            .map(|m| format!("{{\"start\":{},\"end\":{},\"delta\":{}}}", m.range.start, m.range.end, m.delta))
+            .collect::<Vec<String>>();
+        format!("[{}]", maps.join(","))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AlmanacError {
+    Parse { line: usize, text: String },
+    DuplicateMap { source: String, destination: String },
+    NoPath { source: String, destination: String },
+    Invalid(Issue),
+}
+impl fmt::Display for AlmanacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlmanacError::Parse { line, text } => write!(f, "Couldn't parse line {}: {:?}", line, text),
+            AlmanacError::DuplicateMap { source, destination } => {
+                write!(f, "More than one {}-to-{} map", source, destination)
+            }
+            AlmanacError::NoPath { source, destination } => {
+                write!(f, "No chain of maps converts {} to {}", source, destination)
+            }
+            AlmanacError::Invalid(issue) => write!(f, "Invalid almanac, {}", issue),
+        }
+    }
+}
+impl std::error::Error for AlmanacError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    Overlap(Range<i64>, Range<i64>),
+    // A map with a length of zero or less
+    EmptyRange(Range<i64>),
+    // Values between two maps that no map covers, these pass through unchanged
+    Gap(Range<i64>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    pub source: String,
+    pub destination: String,
+    pub kind: IssueKind,
+}
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-to-{} map: ", self.source, self.destination)?;
+        match &self.kind {
+            IssueKind::Overlap(a, b) => write!(f, "source ranges {:?} and {:?} overlap", a, b),
+            IssueKind::EmptyRange(range) => {
+                write!(f, "range starting at {} has length {}", range.start, range.end - range.start)
+            }
+            IssueKind::Gap(range) => write!(f, "no map covers {:?}", range),
+        }
+    }
+}
+
+// One "<source>-to-<destination> map:" section of the almanac.
+#[derive(Clone, Debug)]
+pub struct Conversion {
+    pub source: String,
+    pub destination: String,
+    mappings: Mappings,
 }
 
 #[derive(Debug, Default)]
 pub struct Output {
     seeds: Vec<i64>,
-    mappings: Vec<Mappings>
+    conversions: Vec<Conversion>
+}
+impl Output {
+    fn add_conversion(&mut self, conversion: Conversion) -> Result<(), AlmanacError> {
+        let duplicate = self.conversions.iter().any(|existing| {
+            existing.source == conversion.source && existing.destination == conversion.destination
+        });
+        if duplicate {
+            return Err(AlmanacError::DuplicateMap {
+                source: conversion.source,
+                destination: conversion.destination,
+            });
+        }
+
+        self.conversions.push(conversion);
+        Ok(())
+    }
+
+    // Shortest chain of maps from one category to another, found with a
+    // breadth first search over the categories.
+    pub fn path(&self, source: &str, destination: &str) -> Result<Vec<&Mappings>, AlmanacError> {
+        let mut came_from: HashMap<&str, &Conversion> = HashMap::new();
+        let mut queue = VecDeque::from([source]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == destination {
+                let mut path = vec![];
+                let mut current = destination;
+                while current != source {
+                    let conversion = came_from[current];
+                    path.push(&conversion.mappings);
+                    current = &conversion.source;
+                }
+                path.reverse();
+                return Ok(path);
+            }
+
+            for conversion in self.conversions.iter().filter(|c| c.source == category) {
+                let next = conversion.destination.as_str();
+                if next != source && !came_from.contains_key(next) {
+                    came_from.insert(next, conversion);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        Err(AlmanacError::NoPath {
+            source: source.to_string(),
+            destination: destination.to_string(),
+        })
+    }
+
+    pub fn validate(&self) -> Vec<Issue> {
+        self.conversions
+            .iter()
+            .flat_map(|conversion| {
+                conversion.mappings.issues().into_iter().map(|kind| Issue {
+                    source: conversion.source.clone(),
+                    destination: conversion.destination.clone(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+
+    // Warns about overlapping and empty ranges, with `strict` an overlap is an
+    // error instead. Gaps are normal in an almanac so they are left out.
+    pub fn check(&self, strict: bool) -> Result<(), AlmanacError> {
+        for issue in self.validate() {
+            match issue.kind {
+                IssueKind::Gap(_) => {}
+                IssueKind::Overlap(..) if strict => return Err(AlmanacError::Invalid(issue)),
+                _ => eprintln!("Warning: {}", issue),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn normalize(&mut self) {
+        for conversion in self.conversions.iter_mut() {
+            conversion.mappings = conversion.mappings.normalized();
+        }
+    }
+
+    // Converts between any two categories as a single map.
+    pub fn convert(&self, source: &str, destination: &str) -> Result<Mappings, AlmanacError> {
+        Ok(self
+            .path(source, destination)?
+            .into_iter()
+            .fold(Mappings::default(), |composed, map| composed.then(map)))
+    }
+
+    // The whole seed to location chain as a single map.
+    pub fn composed(&self) -> Mappings {
+        self.convert("seed", "location")
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn seed_to_location(&self) -> Vec<&Mappings> {
+        self.path("seed", "location")
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
 }
 
 #[derive(Default, Debug)]
 pub struct Day05;
-impl Solution for Day05 {
-    type ParsedInput = Output;
-
-    fn parse_input(input_lines: &str) -> Self::ParsedInput {
+impl Day05 {
+    pub fn try_parse(input_lines: &str) -> Result<Output, AlmanacError> {
         let mut output = Output::default();
-        let lines: Vec<String> = input_lines
-                                .lines()
-                                .map(String::from)
-                                .filter(|s| !s.is_empty())
-                                .collect();
-        let seeds = lines[0].split_once(": ").unwrap().1;
-        output.seeds = seeds.split(' ').map(|seed| seed.parse().unwrap()).collect();
-        let mut curr_map = Mappings::default();
-        for line in lines[2..].iter() {
-            if line.contains(":") {
-                output.mappings.push(curr_map);
-                curr_map = Mappings::default();
+        let mut lines = input_lines
+                            .lines()
+                            .enumerate()
+                            .filter(|(_, line)| !line.trim().is_empty());
+        let parse_error = |(idx, line): (usize, &str)| AlmanacError::Parse {
+            line: idx + 1,
+            text: line.to_string(),
+        };
+
+        let first = lines.next().unwrap_or((0, ""));
+        let seeds = first.1.strip_prefix("seeds:").ok_or_else(|| parse_error(first))?;
+        output.seeds = seeds
+                        .split_whitespace()
+                        .map(|seed| seed.parse().map_err(|_| parse_error(first)))
+                        .collect::<Result<Vec<i64>, AlmanacError>>()?;
+
+        let mut current: Option<Conversion> = None;
+        for (idx, line) in lines {
+            if let Some(header) = line.trim().strip_suffix(" map:") {
+                let (source, destination) = header
+                                                .split_once("-to-")
+                                                .ok_or_else(|| parse_error((idx, line)))?;
+                if let Some(conversion) = current.take() {
+                    output.add_conversion(conversion)?;
+                }
+                current = Some(Conversion {
+                    source: source.to_string(),
+                    destination: destination.to_string(),
+                    mappings: Mappings::default(),
+                });
                 continue;
             }
 
-            let nums: Vec<i64> = line.split(' ').map(|n| n.parse().unwrap()).collect();
-            curr_map.add(nums[0], nums[1], nums[2]);
+            let nums = line
+                        .split_whitespace()
+                        .map(|n| n.parse::<i64>().ok())
+                        .collect::<Option<Vec<i64>>>()
+                        .filter(|nums| nums.len() == 3)
+                        .ok_or_else(|| parse_error((idx, line)))?;
+            let conversion = current.as_mut().ok_or_else(|| parse_error((idx, line)))?;
+            conversion.mappings.add(nums[0], nums[1], nums[2]);
+        }
+        if let Some(conversion) = current {
+            output.add_conversion(conversion)?;
         }
-        if !curr_map.maps.is_empty() {
-            output.mappings.push(curr_map);
+
+        Ok(output)
+    }
+
+    pub fn print_explanation(input_lines: &str, format: OutputFormat) -> Result<(), ExplainError> {
+        let composed = Day05::parse_input(input_lines).composed();
+        match format {
+            OutputFormat::Text => println!("seed-to-location map:\n{}", composed.to_table()),
+            OutputFormat::Json => println!("{}", composed.to_json()),
+            OutputFormat::Html => return Err(ExplainError::UnsupportedFormat { day: 5, format }),
         }
-        output
+        Ok(())
+    }
+}
+impl Solution for Day05 {
+    type ParsedInput = Output;
+
+    fn parse_input(input_lines: &str) -> Self::ParsedInput {
+        Day05::try_parse(input_lines).unwrap_or_else(|err| panic!("{}", err))
     }
 
     fn part_one(parsed_input: &mut Self::ParsedInput) -> String {
         let mut min = i64::MAX;
+        let chain = parsed_input.seed_to_location();
 
         for seed in parsed_input.seeds.iter() {
             let mut curr = *seed;
-            for map in parsed_input.mappings.iter() {
+            for map in chain.iter() {
                 curr = map.apply_map(curr);
             }
             min = min.min(curr);
@@ -91,17 +458,173 @@ impl Solution for Day05 {
     }
 
     fn part_two(parsed_input: &mut Self::ParsedInput) -> String {
-        let mut min = i64::MAX;
-        for seed_range in parsed_input.seeds.chunks(2) {
-            for seed in seed_range[0]..seed_range[0]+seed_range[1] {
-                let mut curr = seed;
-                for map in parsed_input.mappings.iter() {
-                    curr = map.apply_map(curr);
-                }
-                min = min.min(curr);
+        let seed_ranges = parsed_input
+                            .seeds
+                            .chunks(2)
+                            .map(|seed_range| seed_range[0]..seed_range[0] + seed_range[1])
+                            .filter(|seed_range| !seed_range.is_empty())
+                            .collect::<Vec<Range<i64>>>();
+
+        let locations = parsed_input
+                            .seed_to_location()
+                            .into_iter()
+                            .fold(seed_ranges, |ranges, map| map.apply_ranges(ranges));
+
+        let min = locations
+                    .iter()
+                    .map(|range| range.start)
+                    .min()
+                    .unwrap_or(i64::MAX);
+        min.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::day05::{AlmanacError, Day05, Issue, IssueKind, Mappings};
+    use crate::Solution;
+
+    const EXAMPLE: &str = "seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    #[test]
+    fn validate_composed_and_inverse() {
+        let almanac = Day05::parse_input(EXAMPLE);
+        let composed = almanac.composed();
+
+        for seed in -5..120 {
+            let location = almanac
+                .seed_to_location()
+                .into_iter()
+                .fold(seed, |curr, map| map.apply_map(curr));
+            assert_eq!(composed.apply_map(seed), location);
+            assert!(composed.preimage(location).contains(&seed));
+        }
+
+        let inverse = composed.inverse().unwrap();
+        assert_eq!(inverse.apply_map(46), 82);
+        assert_eq!(composed.then(&inverse), Default::default());
+    }
+
+    #[test]
+    fn validate_category_queries() {
+        let almanac = Day05::parse_input(EXAMPLE);
+        let soil_to_water = almanac.convert("soil", "water").unwrap();
+        assert_eq!(soil_to_water.apply_map(81), 81);
+        assert_eq!(soil_to_water.apply_map(14), 49);
+        assert_eq!(almanac.path("seed", "humidity").unwrap().len(), 6);
+
+        assert_eq!(
+            almanac.convert("location", "seed").unwrap_err(),
+            AlmanacError::NoPath {
+                source: "location".to_string(),
+                destination: "seed".to_string(),
             }
+        );
+
+        let duplicated = format!("{}\n\nseed-to-soil map:\n1 2 3", EXAMPLE);
+        assert_eq!(
+            Day05::try_parse(&duplicated).unwrap_err(),
+            AlmanacError::DuplicateMap {
+                source: "seed".to_string(),
+                destination: "soil".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn validate_issues_and_normalize() {
+        let mut mappings = Mappings::default();
+        mappings.add(10, 0, 5);
+        mappings.add(20, 3, 4);
+        mappings.add(0, 9, 0);
+        mappings.add(17, 12, 2);
+        mappings.add(19, 14, 1);
+        assert_eq!(
+            mappings.issues(),
+            vec![
+                IssueKind::EmptyRange(9..9),
+                IssueKind::Overlap(0..5, 3..7),
+                IssueKind::Gap(7..12),
+            ]
+        );
+
+        let normalized = mappings.normalized();
+        assert_eq!(normalized.issues(), vec![IssueKind::Gap(7..12)]);
+        assert_eq!(normalized.to_table(), "10 0 5\n22 5 2\n17 12 3");
+
+        let almanac = Day05::parse_input(EXAMPLE);
+        assert!(almanac.check(true).is_ok());
+    }
+
+    #[test]
+    fn validate_strict_check_rejects_overlaps() {
+        let overlapping = Day05::parse_input("seeds: 1\n\nseed-to-soil map:\n50 0 10\n60 5 10\n0 20 0");
+        assert_eq!(
+            overlapping.check(true),
+            Err(AlmanacError::Invalid(Issue {
+                source: "seed".to_string(),
+                destination: "soil".to_string(),
+                kind: IssueKind::Overlap(0..10, 5..15),
+            }))
+        );
+        // Without strict both the overlap and the empty range are only warnings
+        assert_eq!(overlapping.check(false), Ok(()));
+    }
+
+    #[test]
+    fn validate_apply_ranges_matches_apply_map() {
+        let mut mappings = Mappings::default();
+        mappings.add(100, 10, 10);
+        // Overlaps the end of the first map, which wins where both apply
+        mappings.add(200, 15, 10);
+        mappings.add(0, 30, 5);
+        mappings.add(40, 32, 2);
+
+        for range in [0..50, 12..17, 18..28, 5..11, 31..34, 24..26, 40..45, 9..10, 20..20] {
+            let by_range = mappings
+                .apply_ranges(vec![range.clone()])
+                .into_iter()
+                .flatten()
+                .collect::<BTreeSet<i64>>();
+            let by_value = range.clone().map(|v| mappings.apply_map(v)).collect::<BTreeSet<i64>>();
+            assert_eq!(by_range, by_value, "{:?}", range);
         }
 
-        min.to_string()
+        let (p1, p2) = Day05::solve(EXAMPLE);
+        assert_eq!((p1.as_str(), p2.as_str()), ("35", "46"));
     }
-}
\ No newline at end of file
+}
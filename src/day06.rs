@@ -1,32 +1,243 @@
+use std::fmt;
+
+use crate::checked::{Accumulator, AccumulateExt};
+use crate::report::{ExplainError, OutputFormat, DIM, GREEN, RESET};
 use crate::Solution;
 
 #[derive(Debug)]
 pub struct Race {
-    times: Vec<i64>,
-    distances: Vec<i64>
+    times: Vec<u128>,
+    distances: Vec<u128>
 }
 impl Race {
-    pub fn cal_ways_to_win(&self) -> Vec<i64> {
-        let mut counts: Vec<i64> = Vec::new();
-        for (idx, t) in self.times.iter().enumerate() {
-            let dist = self.distances[idx];
-            let mut count = 0;
-            for v in 1..=*t {
-                let curr_dist = v * (t - v);
-                if curr_dist > dist {
-                    count += 1;
-                }
-            }
-            counts.push(count);
+    pub fn cal_ways_to_win(&self) -> Result<Vec<u128>, RaceTooLong> {
+        self.times
+            .iter()
+            .zip(self.distances.iter())
+            .map(|(&t, &d)| {
+                Ok(match winning_holds(t, d)? {
+                    Some((first, last)) => last - first + 1,
+                    None => 0,
+                })
+            })
+            .collect()
+    }
+
+    pub fn analyse(&self) -> Result<Vec<RaceAnalysis>, RaceTooLong> {
+        self.times
+            .iter()
+            .zip(self.distances.iter())
+            .map(|(&time, &record)| RaceAnalysis::new(time, record))
+            .collect()
+    }
+}
+
+// The best distance for a race this long doesn't fit in a u128.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaceTooLong {
+    pub time: u128,
+}
+impl fmt::Display for RaceTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "A {} ms race can go further than a u128 can hold", self.time)
+    }
+}
+impl std::error::Error for RaceTooLong {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RaceAnalysis {
+    pub time: u128,
+    pub record: u128,
+    pub optimal_hold: u128,
+    pub max_distance: u128,
+    pub winning_holds: Option<(u128, u128)>,
+    pub ways_to_win: u128,
+    // How far the best possible run beats the record by, negative if it can't
+    pub margin: i128,
+}
+impl RaceAnalysis {
+    pub fn new(time: u128, record: u128) -> Result<Self, RaceTooLong> {
+        let too_long = RaceTooLong { time };
+        let optimal_hold = time / 2;
+        let max_distance = max_distance(time).ok_or(too_long)?;
+        let winning_holds = winning_holds(time, record)?;
+        let margin = if max_distance >= record {
+            i128::try_from(max_distance - record)
+        } else {
+            i128::try_from(record - max_distance).map(|behind| -behind)
+        };
+        Ok(RaceAnalysis {
+            time,
+            record,
+            optimal_hold,
+            max_distance,
+            winning_holds,
+            ways_to_win: winning_holds.map_or(0, |(first, last)| last - first + 1),
+            margin: margin.map_err(|_| too_long)?,
+        })
+    }
+
+    // (hold, distance) at up to `samples` evenly spaced hold times, plus the
+    // winning boundaries so they always show up.
+    pub fn curve(&self, samples: usize) -> Vec<(u128, u128)> {
+        let samples = samples.max(2) as u128;
+        let mut holds = if self.time < samples {
+            (0..=self.time).collect::<Vec<u128>>()
+        } else {
+            (0..samples).map(|i| i * self.time / (samples - 1)).collect()
+        };
+        if let Some((first, last)) = self.winning_holds {
+            holds.extend([first, last]);
         }
+        holds.sort();
+        holds.dedup();
+
+        holds
+            .into_iter()
+            .map(|hold| (hold, distance(self.time, hold)))
+            .collect()
+    }
+
+    // One bar per curve point scaled to `width`, winning holds in green.
+    pub fn plot(&self, samples: usize, width: usize) -> String {
+        let scale = self.max_distance.max(1);
+        let hold_width = self.time.to_string().len();
+        let distance_width = self.max_distance.to_string().len();
+
+        let mut out = String::new();
+        for (hold, distance) in self.curve(samples) {
+            let bar = "#".repeat((distance * width as u128 / scale) as usize);
+            let colour = if distance > self.record { GREEN } else { DIM };
+            out.push_str(&format!(
+                "{:>hw$} | {:>dw$} | {}{}{}\n",
+                hold,
+                distance,
+                colour,
+                bar,
+                RESET,
+                hw = hold_width,
+                dw = distance_width
+            ));
+        }
+        out
+    }
 
-        counts
+    fn summary(&self) -> String {
+        let wins = match self.winning_holds {
+            Some((first, last)) => format!("wins holding {}..={} ({} ways)", first, last, self.ways_to_win),
+            None => "can't be won".to_string(),
+        };
+        format!(
+            "time {}, record {}: {}, best is holding {} for {} (margin {})",
+            self.time, self.record, wins, self.optimal_hold, self.max_distance, self.margin
+        )
     }
+
+    fn to_json(&self, samples: usize) -> String {
+        let (first, last) = match self.winning_holds {
+            Some((first, last)) => (first.to_string(), last.to_string()),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        let curve = self
+            .curve(samples)
+            .into_iter()
+            .map(|(hold, distance)| format!("[{},{}]", hold, distance))
+            .collect::<Vec<String>>()
+            .join(",");
+        format!(
+            "{{\"time\":{},\"record\":{},\"optimal_hold\":{},\"max_distance\":{},\"first_win\":{},\"last_win\":{},\"ways_to_win\":{},\"margin\":{},\"curve\":[{}]}}",
+            self.time,
+            self.record,
+            self.optimal_hold,
+            self.max_distance,
+            first,
+            last,
+            self.ways_to_win,
+            self.margin,
+            curve
+        )
+    }
+}
+
+// Only used once `max_distance` is known to fit, nothing else goes further.
+fn distance(time: u128, hold: u128) -> u128 {
+    hold * (time - hold)
+}
+
+// Holding for half the race (rounded down), None if it doesn't fit.
+fn max_distance(time: u128) -> Option<u128> {
+    let half = time / 2;
+    half.checked_mul(time - half)
 }
 
+// The first and last hold times v for which v * (t - v) > d. Writing t as
+// 2x + p with p the parity and v as x - k, the distance is
+// x(x + p) - k(k + p), so the holds that win are the k with k(k + p) < e where
+// e = x(x + p) - d is how far the best hold beats the record. The integer
+// square root of e is within one of the largest such k and is nudged onto it,
+// which also gets the boundary right when the roots are integers (those holds
+// only tie the record). Nothing is squared that's bigger than e, so only the
+// best distance itself can overflow.
+pub fn winning_holds(t: u128, d: u128) -> Result<Option<(u128, u128)>, RaceTooLong> {
+    let (x, p) = (t / 2, t % 2);
+    let best = max_distance(t).ok_or(RaceTooLong { time: t })?;
+    if best <= d {
+        return Ok(None);
+    }
+
+    let e = best - d;
+    // k(k + p) with k < 2^64 + 1, so it only overflows when it's past e anyway
+    let below_e = |k: u128| k.checked_mul(k + p).is_some_and(|kk| kk < e);
+    let mut k = e.isqrt();
+    while !below_e(k) {
+        k -= 1;
+    }
+    while below_e(k + 1) {
+        k += 1;
+    }
+
+    let first = x - k;
+    Ok(Some((first, t - first)))
+}
 
 #[derive(Debug)]
 pub struct Day06;
+impl Day06 {
+    const CURVE_SAMPLES: usize = 21;
+    const PLOT_WIDTH: usize = 50;
+
+    pub fn print_explanation(input_lines: &str, format: OutputFormat) -> Result<(), ExplainError> {
+        let lines: Vec<String> = input_lines.lines().map(String::from).collect();
+        let parses = [
+            ("part 1", parse_for_p1(&lines).analyse().unwrap_or_else(|err| panic!("{}", err))),
+            ("part 2", parse_for_p2(&lines).analyse().unwrap_or_else(|err| panic!("{}", err))),
+        ];
+        match format {
+            OutputFormat::Text => {
+                for (name, races) in parses.iter() {
+                    println!("{}:", name);
+                    for race in races {
+                        println!("{}", race.summary());
+                        print!("{}", race.plot(Day06::CURVE_SAMPLES, Day06::PLOT_WIDTH));
+                    }
+                }
+            }
+            OutputFormat::Json => {
+                let [(_, part_one), (_, part_two)] = &parses;
+                let to_json = |races: &Vec<RaceAnalysis>| {
+                    races
+                        .iter()
+                        .map(|race| race.to_json(Day06::CURVE_SAMPLES))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                };
+                println!("{{\"part_one\":[{}],\"part_two\":[{}]}}", to_json(part_one), to_json(part_two));
+            }
+            OutputFormat::Html => return Err(ExplainError::UnsupportedFormat { day: 6, format }),
+        }
+        Ok(())
+    }
+}
 impl Solution for Day06 {
     type ParsedInput = Vec<Race>;
 
@@ -41,26 +252,26 @@ impl Solution for Day06 {
     fn part_one(parsed_input: &mut Self::ParsedInput) -> String {
         let race = &parsed_input[0];
 
-        let counts = race.cal_ways_to_win();
+        let counts = race.cal_ways_to_win().unwrap_or_else(|err| panic!("{}", err));
 
-        counts.iter().fold(1, |acc, x| acc * x).to_string()
+        counts.into_iter().product_with(&Accumulator::new(6, 1)).to_string()
     }
 
     fn part_two(parsed_input: &mut Self::ParsedInput) -> String {
         let race = &parsed_input[1];
         
-        let counts = race.cal_ways_to_win();
+        let counts = race.cal_ways_to_win().unwrap_or_else(|err| panic!("{}", err));
 
-        counts.iter().fold(1, |acc, x| acc * x).to_string()
+        counts.into_iter().product_with(&Accumulator::new(6, 2)).to_string()
     }
 }
 
-pub fn parse_for_p1(lines: &Vec<String>) -> Race {
+pub fn parse_for_p1(lines: &[String]) -> Race {
     let times = lines[0].split_once(":").unwrap();
-    let times_values: Vec<i64> = times.1.split_whitespace().map(|x| x.parse().unwrap()).collect();
+    let times_values: Vec<u128> = times.1.split_whitespace().map(|x| x.parse().unwrap()).collect();
 
     let dists = lines[1].split_once(":").unwrap();
-    let dists_values: Vec<i64> = dists.1.split_whitespace().map(|x| x.parse().unwrap()).collect();
+    let dists_values: Vec<u128> = dists.1.split_whitespace().map(|x| x.parse().unwrap()).collect();
 
     assert_eq!(times_values.len(), dists_values.len());
     Race { 
@@ -69,20 +280,71 @@ pub fn parse_for_p1(lines: &Vec<String>) -> Race {
     }
 }
 
-pub fn parse_for_p2(lines: &Vec<String>) -> Race {
+pub fn parse_for_p2(lines: &[String]) -> Race {
     let times = lines[0].split_once(":").unwrap();
     let dists = lines[1].split_once(":").unwrap();
 
     let mut times_val = String::from(times.1);
     times_val.retain(|c| !c.is_whitespace());
-    let parsed_time: i64 = times_val.parse().unwrap();
+    let parsed_time: u128 = times_val.parse().unwrap();
 
     let mut dist_val = String::from(dists.1);
     dist_val.retain(|c| !c.is_whitespace());
-    let parsed_dist: i64 = dist_val.parse().unwrap();
+    let parsed_dist: u128 = dist_val.parse().unwrap();
 
     Race { 
         times: vec![parsed_time], 
         distances: vec![parsed_dist] 
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day06::{winning_holds, RaceAnalysis, RaceTooLong};
+
+    #[test]
+    fn validate_exact_boundaries() {
+        assert_eq!(winning_holds(7, 9), Ok(Some((2, 5))));
+        assert_eq!(winning_holds(15, 40), Ok(Some((4, 11))));
+        // 10 * 20 = 200 exactly, so the integer roots don't win
+        assert_eq!(winning_holds(30, 200), Ok(Some((11, 19))));
+        assert_eq!(winning_holds(71530, 940200), Ok(Some((14, 71516))));
+        assert_eq!(winning_holds(10, 25), Ok(None));
+        assert_eq!(winning_holds(0, 0), Ok(None));
+
+        let t = 1 << 60;
+        let d = (t / 2 - 3) * (t / 2 + 3);
+        assert_eq!(winning_holds(t, d), Ok(Some((t / 2 - 2, t / 2 + 2))));
+
+        // t * t would overflow from here on, only the best distance has to fit
+        let t: u128 = 1 << 64;
+        assert_eq!(winning_holds(t, 10), Ok(Some((1, t - 1))));
+        // holding for 1 only ties
+        assert_eq!(winning_holds(t + 1, t), Ok(Some((2, t - 1))));
+        let d = (t / 2 - 3) * (t / 2 + 3);
+        assert_eq!(winning_holds(t, d), Ok(Some((t / 2 - 2, t / 2 + 2))));
+        assert_eq!(winning_holds(t, t / 2 * (t / 2)), Ok(None));
+
+        let t = (1 << 65) + 1;
+        assert_eq!(winning_holds(t, 0), Err(RaceTooLong { time: t }));
+    }
+
+    #[test]
+    fn validate_analysis() {
+        let race = RaceAnalysis::new(30, 200).unwrap();
+        assert_eq!(race.optimal_hold, 15);
+        assert_eq!(race.max_distance, 225);
+        assert_eq!(race.winning_holds, Some((11, 19)));
+        assert_eq!(race.ways_to_win, 9);
+        assert_eq!(race.margin, 25);
+
+        let curve = race.curve(4);
+        assert_eq!(curve, vec![(0, 0), (10, 200), (11, 209), (19, 209), (20, 200), (30, 0)]);
+
+        let lost = RaceAnalysis::new(4, 10).unwrap();
+        assert_eq!((lost.winning_holds, lost.ways_to_win, lost.margin), (None, 0, -6));
+
+        let t = (1 << 65) + 1;
+        assert_eq!(RaceAnalysis::new(t, 0), Err(RaceTooLong { time: t }));
+    }
+}
@@ -1,24 +1,50 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::{self, Debug},
+    hash::Hash,
+};
 
 use itertools::Itertools;
 use Card::*;
 
+use crate::checked::{Accumulator, AccumulateExt};
+use crate::report::{json_string, ExplainError, OutputFormat};
 use crate::Solution;
 
-#[derive(Eq, PartialEq, Debug, Ord, PartialOrd)]
-enum HandType {
+#[derive(Eq, PartialEq, Debug, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum HandType {
     HighCard,
     OnePair,
     TwoPair,
     ThreeOfAKind,
+    Straight,
+    Flush,
     FullHouse,
     FourOfAKind,
+    StraightFlush,
+    RoyalFlush,
     FiveOfAKind,
 }
+impl HandType {
+    // The best type made only out of groups of equal cards, `groups` is the
+    // size of each group from largest to smallest.
+    fn from_groups(groups: &[usize]) -> HandType {
+        use HandType::*;
+        match groups {
+            [n, ..] if *n >= 5 => FiveOfAKind,
+            [4, ..] => FourOfAKind,
+            [3, n, ..] if *n >= 2 => FullHouse,
+            [3, ..] => ThreeOfAKind,
+            [2, 2, ..] => TwoPair,
+            [2, ..] => OnePair,
+            _ => HighCard,
+        }
+    }
+}
 
-#[derive(Eq, PartialEq, Debug, Ord, PartialOrd, Hash)]
-enum Card {
-    Joker, 
+#[derive(Eq, PartialEq, Debug, Ord, PartialOrd, Clone, Copy, Hash)]
+pub enum Card {
     Num(u32),
     Jack,
     Queen,
@@ -26,18 +52,32 @@ enum Card {
     Ace,
 }
 impl Card {
-    fn parse_cards(cards: &str) -> Vec<Card> {
-        cards
-            .chars()
-            .filter_map(|c| c.try_into().ok())
-            .collect()
-    }
+    const ALL: [Card; 13] = [
+        Num(2), Num(3), Num(4), Num(5), Num(6), Num(7), Num(8), Num(9), Num(10),
+        Jack, Queen, King, Ace,
+    ];
 
-    fn parse_cards_p2(cards: &str) -> Vec<Card> {
-        Self::parse_cards(cards)
-            .into_iter()
-            .map(|c| if c == Jack { Joker } else { c })
-            .collect()
+    // 2 to 14, aces high
+    fn value(&self) -> u8 {
+        match self {
+            Num(n) => *n as u8,
+            Jack => 11,
+            Queen => 12,
+            King => 13,
+            Ace => 14,
+        }
+    }
+}
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Num(10) => write!(f, "T"),
+            Num(n) => write!(f, "{}", n),
+            Jack => write!(f, "J"),
+            Queen => write!(f, "Q"),
+            King => write!(f, "K"),
+            Ace => write!(f, "A"),
+        }
     }
 }
 impl TryFrom<char> for Card {
@@ -50,125 +90,729 @@ impl TryFrom<char> for Card {
             'Q' => Ok(Queen),
             'J' => Ok(Jack),
             'T' => Ok(Num(10)),
-            c => c.to_digit(10).map(|d| Num(d)).ok_or(()),
+            c => c.to_digit(10).map(Num).ok_or(()),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+impl TryFrom<char> for Suit {
+    type Error = ();
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            'C' => Ok(Suit::Clubs),
+            'D' => Ok(Suit::Diamonds),
+            'H' => Ok(Suit::Hearts),
+            'S' => Ok(Suit::Spades),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub struct PlayingCard {
+    pub rank: Card,
+    pub suit: Suit,
+}
+impl TryFrom<&str> for PlayingCard {
+    type Error = ();
+
+    // "AS", "TD" or "10D"
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut chars = value.chars();
+        let suit = chars.next_back().ok_or(())?.try_into()?;
+        let rank = match chars.as_str() {
+            "10" => Num(10),
+            rank if rank.len() == 1 => rank.chars().next().unwrap().try_into()?,
+            _ => return Err(()),
+        };
+        if rank.value() < 2 {
+            return Err(());
+        }
+        Ok(PlayingCard { rank, suit })
+    }
+}
+
+// How hands are dealt, scored and compared.
+pub trait Rules {
+    type Card: Clone + Eq + Hash + Debug;
+
+    fn hand_size(&self) -> usize;
+
+    fn parse_cards(&self, input: &str) -> Option<Vec<Self::Card>>;
+
+    fn is_wild(&self, card: &Self::Card) -> bool;
+
+    // The cards a wild card may stand in for.
+    fn substitutes(&self) -> Vec<Self::Card>;
+
+    // Higher is stronger, used to break ties between hands of the same type.
+    fn strength(&self, card: &Self::Card) -> u8;
+
+    // Every hand type of the rule set from weakest to strongest.
+    fn ladder(&self) -> &[HandType];
+
+    // The type of a hand where no card is wild.
+    fn natural_type(&self, cards: &[Self::Card]) -> HandType;
+
+    // Compared when two hands have the same type, by default the strength of
+    // the cards in the order they were dealt.
+    fn tie_break(&self, cards: &[Self::Card]) -> Vec<u8> {
+        cards.iter().map(|card| self.strength(card)).collect()
+    }
+
+    fn type_rank(&self, hand_type: HandType) -> usize {
+        self.ladder()
+            .iter()
+            .position(|t| *t == hand_type)
+            .unwrap_or_else(|| panic!("{:?} isn't part of the rule set", hand_type))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Evaluation<C> {
+    pub hand_type: HandType,
+    // The hand with every wild card replaced by what it stands in for
+    pub substituted: Vec<C>,
+}
+
+// Tries every way of filling in the wild cards and keeps the best. Which
+// wild card gets which substitute doesn't change the type, so only multisets
+// of substitutes are tried.
+pub fn evaluate<R: Rules>(rules: &R, cards: &[R::Card]) -> Evaluation<R::Card> {
+    let wild = cards
+        .iter()
+        .positions(|card| rules.is_wild(card))
+        .collect::<Vec<usize>>();
+    if wild.is_empty() {
+        return Evaluation { hand_type: rules.natural_type(cards), substituted: cards.to_vec() };
+    }
+
+    rules
+        .substitutes()
+        .into_iter()
+        .combinations_with_replacement(wild.len())
+        .map(|substitutes| {
+            let mut substituted = cards.to_vec();
+            for (&idx, card) in wild.iter().zip(substitutes) {
+                substituted[idx] = card;
+            }
+            Evaluation { hand_type: rules.natural_type(&substituted), substituted }
+        })
+        .max_by_key(|evaluation| rules.type_rank(evaluation.hand_type))
+        .unwrap_or_else(|| Evaluation { hand_type: rules.natural_type(cards), substituted: cards.to_vec() })
+}
+
+// Camel Cards, optionally with jacks played as jokers: wild, but the weakest
+// card in a tie-break.
+#[derive(Clone, Copy, Debug)]
+pub struct CamelCards {
+    jokers: bool,
+}
+impl CamelCards {
+    pub fn standard() -> Self {
+        CamelCards { jokers: false }
+    }
+
+    pub fn with_jokers() -> Self {
+        CamelCards { jokers: true }
+    }
+}
+impl Rules for CamelCards {
+    type Card = Card;
+
+    fn hand_size(&self) -> usize {
+        5
+    }
+
+    fn parse_cards(&self, input: &str) -> Option<Vec<Card>> {
+        input.chars().map(|c| c.try_into().ok()).collect()
+    }
+
+    fn is_wild(&self, card: &Card) -> bool {
+        self.jokers && *card == Jack
+    }
+
+    fn substitutes(&self) -> Vec<Card> {
+        Card::ALL.into_iter().filter(|card| !self.is_wild(card)).collect()
+    }
+
+    fn strength(&self, card: &Card) -> u8 {
+        if self.is_wild(card) { 1 } else { card.value() }
+    }
+
+    fn ladder(&self) -> &[HandType] {
+        use HandType::*;
+        &[HighCard, OnePair, TwoPair, ThreeOfAKind, FullHouse, FourOfAKind, FiveOfAKind]
+    }
+
+    fn natural_type(&self, cards: &[Card]) -> HandType {
+        let groups = cards.iter().counts().into_values().sorted().rev().collect::<Vec<usize>>();
+        HandType::from_groups(&groups)
+    }
+}
+
+// Standard five card poker with a single deck, so no five of a kind.
+#[derive(Clone, Copy, Debug)]
+pub struct Poker;
+impl Poker {
+    // The high card of a straight, an ace plays low in A-2-3-4-5
+    fn straight_high(cards: &[PlayingCard]) -> Option<u8> {
+        let values = cards.iter().map(|card| card.rank.value()).sorted().dedup().collect::<Vec<u8>>();
+        match values.as_slice() {
+            [2, 3, 4, 5, 14] => Some(5),
+            [low, .., high] if values.len() == 5 && high - low == 4 => Some(*high),
+            _ => None,
+        }
+    }
+}
+impl Rules for Poker {
+    type Card = PlayingCard;
+
+    fn hand_size(&self) -> usize {
+        5
+    }
+
+    // A single deck, so a card can't be dealt twice
+    fn parse_cards(&self, input: &str) -> Option<Vec<PlayingCard>> {
+        let cards = input
+            .split_whitespace()
+            .map(|card| card.try_into().ok())
+            .collect::<Option<Vec<PlayingCard>>>()?;
+        cards.iter().all_unique().then_some(cards)
+    }
+
+    fn is_wild(&self, _card: &PlayingCard) -> bool {
+        false
+    }
+
+    fn substitutes(&self) -> Vec<PlayingCard> {
+        vec![]
+    }
+
+    fn strength(&self, card: &PlayingCard) -> u8 {
+        card.rank.value()
+    }
+
+    fn ladder(&self) -> &[HandType] {
+        use HandType::*;
+        &[
+            HighCard, OnePair, TwoPair, ThreeOfAKind, Straight, Flush, FullHouse, FourOfAKind,
+            StraightFlush, RoyalFlush,
+        ]
+    }
+
+    fn natural_type(&self, cards: &[PlayingCard]) -> HandType {
+        use HandType::*;
+        let groups = cards.iter().counts_by(|card| card.rank).into_values().sorted().rev().collect::<Vec<usize>>();
+        let flush = cards.iter().map(|card| card.suit).all_equal();
+        match (Poker::straight_high(cards), flush, HandType::from_groups(&groups)) {
+            (Some(14), true, _) => RoyalFlush,
+            (Some(_), true, _) => StraightFlush,
+            (_, _, group_type @ (FourOfAKind | FullHouse)) => group_type,
+            (_, true, _) => Flush,
+            (Some(_), _, _) => Straight,
+            (_, _, group_type) => group_type,
+        }
+    }
+
+    // Bigger groups first then higher ranks, so pairs are compared before
+    // their kickers. A straight only compares its high card.
+    fn tie_break(&self, cards: &[PlayingCard]) -> Vec<u8> {
+        if let Some(high) = Poker::straight_high(cards) {
+            return vec![high];
+        }
+        cards
+            .iter()
+            .counts_by(|card| card.rank.value())
+            .into_iter()
+            .sorted_by_key(|&(value, count)| (count, value))
+            .rev()
+            .map(|(value, _)| value)
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Draw {
+    // Every card is equally likely to be any rank
+    WithReplacement,
+    // Dealt from a deck holding `copies` of each rank
+    WithoutReplacement { copies: u32 },
+}
+
+// Exact counts of how many equally likely deals end up as each hand type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Distribution {
+    pub counts: BTreeMap<HandType, u128>,
+    pub total: u128,
+}
+impl Distribution {
+    pub fn count(&self, hand_type: HandType) -> u128 {
+        self.counts.get(&hand_type).copied().unwrap_or(0)
+    }
+
+    pub fn probability(&self, hand_type: HandType) -> f64 {
+        self.count(hand_type) as f64 / self.total as f64
+    }
+}
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (hand_type, count) in self.counts.iter().rev() {
+            writeln!(
+                f,
+                "{:<12} {:>8}/{} ({:.6})",
+                format!("{:?}", hand_type),
+                count,
+                self.total,
+                self.probability(*hand_type)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn choose(n: u128, k: u128) -> u128 {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+// The hand types a Camel Cards hand ends up as once it is filled up from
+// `given`, found by going through every multiset of the missing cards and
+// weighting it by how many deals produce it. With replacement the deals are
+// ordered, without they are unordered, either way they're equally likely.
+pub fn hand_type_distribution(rules: &CamelCards, draw: Draw, given: &[Card]) -> Distribution {
+    let missing = rules
+        .hand_size()
+        .checked_sub(given.len())
+        .unwrap_or_else(|| panic!("More than {} cards given", rules.hand_size()));
+    let given_counts = given.iter().counts();
+    let available = |card: &Card| match draw {
+        Draw::WithReplacement => u128::MAX,
+        Draw::WithoutReplacement { copies } => {
+            let held = given_counts.get(card).copied().unwrap_or(0) as u128;
+            (copies as u128).checked_sub(held).unwrap_or_else(|| panic!("More than {} {} given", copies, card))
         }
+    };
+
+    let mut counts = BTreeMap::new();
+    let mut total = 0;
+    for drawn in Card::ALL.into_iter().combinations_with_replacement(missing) {
+        let weight = match draw {
+            Draw::WithReplacement => {
+                let mut orderings = (1..=missing as u128).product::<u128>();
+                for k in drawn.iter().counts().into_values() {
+                    orderings /= (1..=k as u128).product::<u128>();
+                }
+                orderings
+            }
+            Draw::WithoutReplacement { .. } => drawn
+                .iter()
+                .counts()
+                .into_iter()
+                .map(|(card, k)| choose(available(card), k as u128))
+                .product(),
+        };
+        if weight == 0 {
+            continue;
+        }
+
+        let cards = given.iter().copied().chain(drawn).collect::<Vec<Card>>();
+        *counts.entry(evaluate(rules, &cards).hand_type).or_insert(0) += weight;
+        total += weight;
     }
+
+    Distribution { counts, total }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct Hand {
+#[derive(Debug)]
+pub struct Hand<C = Card> {
     bid: i32,
-    cards: Vec<Card>,
-    h_type: HandType
+    cards: Vec<C>,
+    h_type: HandType,
+    // What the wild cards stood in for to make `h_type`
+    substituted: Vec<C>,
+    // Position of the type on the rule set's ladder, then the tie-break
+    rank: (usize, Vec<u8>),
 }
-impl Hand {
-    fn new(bid: i32, cards: Vec<Card>, h_type: HandType) -> Hand {
+impl<C: Clone + Eq + Hash + Debug> Hand<C> {
+    // "<cards> <bid>", with the cards in whatever form the rules parse
+    pub fn parse<R: Rules<Card = C>>(rules: &R, input: &str) -> Self {
+        let (card_input, bid_input) = input.rsplit_once(' ').unwrap();
+        let cards = rules
+            .parse_cards(card_input)
+            .filter(|cards| cards.len() == rules.hand_size())
+            .unwrap_or_else(|| panic!("Invalid hand {:?}", card_input));
+        Hand::new(rules, bid_input.parse().unwrap(), cards)
+    }
+
+    fn new<R: Rules<Card = C>>(rules: &R, bid: i32, cards: Vec<C>) -> Self {
+        let Evaluation { hand_type: h_type, substituted } = evaluate(rules, &cards);
+        let rank = (rules.type_rank(h_type), rules.tie_break(&cards));
         Hand {
             bid,
             cards,
             h_type,
+            substituted,
+            rank,
         }
     }
 
-    fn parse(input: &str) -> Self {
-        let (card_input, bid_input) = input.split_once(" ").unwrap();
-        let cards: Vec<Card> = Card::parse_cards(card_input);
-        let hand_type = Hand::calculate_hand_type(&cards);
-    
-        Hand::new(bid_input.parse().unwrap(), cards, hand_type)
-    } 
+    pub fn hand_type(&self) -> HandType {
+        self.h_type
+    }
+}
 
-    fn parse_p2(input: &str) -> Self {
-        let (card_input, bid_input) = input.split_once(" ").unwrap();
-        let cards: Vec<Card> = Card::parse_cards_p2(card_input);
-        let hand_type = Hand::calculate_hand_type(&cards);
-    
-        Hand::new(bid_input.parse().unwrap(), cards, hand_type)
+// Hands are equal when they rank the same, matching Ord, even if the bids or
+// suits differ.
+impl<C> PartialEq for Hand<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank
     }
+}
 
-    fn calculate_hand_type(cards: &Vec<Card>) -> HandType {
-        use HandType::*;
-        let groups = cards.iter().counts();
-        let distinct_count = groups.len();
-        let max_group = groups.values().max().unwrap();
-        let joker_count = groups.get(&Joker).unwrap_or(&0);
-
-        match (distinct_count, max_group, joker_count) {
-            (1, 5, _) => FiveOfAKind,  //
-            (2, 4, 0) => FourOfAKind,  //
-            (2, 4, _) => FiveOfAKind,  // Only 2 values, joker(s) change to match the 
-                                       // other card(s)
-            (2, 3, 0) => FullHouse,    //
-            (2, 3, _) => FiveOfAKind,  // Only 2 values, jokers change to match the 
-                                       // other cards
-            (3, 3, 0) => ThreeOfAKind, //
-            (3, 3, _) => FourOfAKind,  // Three jokers match a singleton, or single 
-                                       // joker matches the triple
-            (3, 2, 0) => TwoPair,      //
-            (3, 2, 1) => FullHouse,    // Singleton joker matches one of the pairs
-            (3, 2, 2) => FourOfAKind,  // Two jokers match the other pair
-            (4, 2, 0) => OnePair,      //
-            (4, 2, _) => ThreeOfAKind, // Two jokers match one of the singletons, 
-                                       // a single joker matches the pair
-            (5, 1, 0) => HighCard,     //
-            (5, 1, 1) => OnePair,      // Joker pairs up with any one of the other values
-            _ => unreachable!(),
-        }
-    }
-}
-
-impl Ord for Hand {
+impl<C> Eq for Hand<C> {}
+
+impl<C> Ord for Hand<C> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.h_type
-            .cmp(&other.h_type)
-            .then(self.cards.cmp(&other.cards))
+        self.rank.cmp(&other.rank)
     }
 }
 
-impl PartialOrd for Hand {
+impl<C> PartialOrd for Hand<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
+// What a hand at `rank` (1 for the weakest) wins
+fn winnings<C>(rank: usize, hand: &Hand<C>, acc: &Accumulator) -> i64 {
+    acc.mul(acc.widen::<usize, i64>(rank), i64::from(hand.bid))
+}
+
+fn total_winnings<C>(hands: &[Hand<C>], acc: &Accumulator) -> i64 {
+    hands
+        .iter()
+        .sorted()
+        .enumerate()
+        .map(|(i, hand)| winnings(i + 1, hand, acc))
+        .sum_with(acc)
+}
+
+fn cards_to_string(cards: &[Card]) -> String {
+    cards.iter().map(Card::to_string).collect()
+}
+
+// Why `lower` ranks below `higher`, either the type or the first card that
+// differs.
+fn tie_break_note(lower: &Hand, higher: &Hand) -> String {
+    if lower.h_type != higher.h_type {
+        return format!("{:?} < {:?}", lower.h_type, higher.h_type);
+    }
+    let (lower_strengths, higher_strengths) = (&lower.rank.1, &higher.rank.1);
+    match (0..lower_strengths.len()).find(|&i| lower_strengths[i] != higher_strengths[i]) {
+        Some(i) => format!("card {}: {} < {}", i + 1, lower.cards[i], higher.cards[i]),
+        None => "tied".to_string(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandExplanation {
+    pub rank: usize,
+    pub cards: String,
+    pub hand_type: HandType,
+    pub substituted: String,
+    pub bid: i32,
+    pub winnings: i64,
+    pub below: Option<String>,
+    pub above: Option<String>,
+}
+impl HandExplanation {
+    fn to_json(&self) -> String {
+        let note = |note: &Option<String>| note.as_deref().map_or("null".to_string(), json_string);
+        format!(
+            "{{\"rank\":{},\"cards\":{},\"hand_type\":{},\"substituted\":{},\"bid\":{},\"winnings\":{},\"below\":{},\"above\":{}}}",
+            self.rank,
+            json_string(&self.cards),
+            json_string(&format!("{:?}", self.hand_type)),
+            json_string(&self.substituted),
+            self.bid,
+            self.winnings,
+            note(&self.below),
+            note(&self.above)
+        )
+    }
+}
+
 pub struct Day07 {
     p1: Vec<Hand>,
     p2: Vec<Hand>,
 }
+impl Day07 {
+    // Hands from weakest to strongest, with how each one compares to the
+    // hands ranked either side of it.
+    pub fn explain(hands: &[Hand], acc: &Accumulator) -> Vec<HandExplanation> {
+        let sorted = hands.iter().sorted().collect::<Vec<&Hand>>();
+        sorted
+            .iter()
+            .enumerate()
+            .map(|(i, hand)| HandExplanation {
+                rank: i + 1,
+                cards: cards_to_string(&hand.cards),
+                hand_type: hand.h_type,
+                substituted: cards_to_string(&hand.substituted),
+                bid: hand.bid,
+                winnings: winnings(i + 1, hand, acc),
+                below: i.checked_sub(1).map(|below| tie_break_note(sorted[below], hand)),
+                above: sorted.get(i + 1).map(|above| tie_break_note(hand, above)),
+            })
+            .collect()
+    }
+
+    pub fn print_explanation(input_lines: &str, format: OutputFormat) -> Result<(), ExplainError> {
+        let input = Day07::parse_input(input_lines);
+        let parts = [
+            ("part 1", Day07::explain(&input.p1, &Accumulator::new(7, 1))),
+            ("part 2", Day07::explain(&input.p2, &Accumulator::new(7, 2))),
+        ];
+        match format {
+            OutputFormat::Text => {
+                for (name, explanations) in parts.iter() {
+                    println!("{}:", name);
+                    print!("{}", explanations_to_text(explanations));
+                }
+            }
+            OutputFormat::Json => {
+                let [(_, part_one), (_, part_two)] = &parts;
+                let to_json = |explanations: &Vec<HandExplanation>| {
+                    explanations
+                        .iter()
+                        .map(HandExplanation::to_json)
+                        .collect::<Vec<String>>()
+                        .join(",")
+                };
+                println!(
+                    "{{\"part_one\":[{}],\"part_two\":[{}]}}",
+                    to_json(part_one),
+                    to_json(part_two)
+                );
+            }
+            OutputFormat::Html => return Err(ExplainError::UnsupportedFormat { day: 7, format }),
+        }
+        Ok(())
+    }
+}
 impl Solution for Day07 {
     type ParsedInput = Self;
 
     fn parse_input(input_lines: &str) -> Self::ParsedInput {
         let p1 = input_lines
                                 .lines()
-                                .map(Hand::parse)
+                                .map(|line| Hand::parse(&CamelCards::standard(), line))
                                 .collect::<Vec<_>>();
         let p2 = input_lines
                                 .lines()
-                                .map(Hand::parse_p2)
+                                .map(|line| Hand::parse(&CamelCards::with_jokers(), line))
                                 .collect::<Vec<_>>();
         Day07 { p1, p2 }
     }
 
     fn part_one(parsed_input: &mut Self::ParsedInput) -> String {
-        let total = parsed_input
-                            .p1
-                            .iter()
-                            .sorted()
-                            .enumerate()
-                            .map(|(i, hand)| (i + 1) as i32 * hand.bid)
-                            .sum::<i32>();
-        total.to_string()
+        total_winnings(&parsed_input.p1, &Accumulator::new(7, 1)).to_string()
     }
 
     fn part_two(parsed_input: &mut Self::ParsedInput) -> String {
-        let total = parsed_input
-                            .p2
-                            .iter()
-                            .sorted()
-                            .enumerate()
-                            .map(|(i, hand)| (i + 1) as i32 * hand.bid)
-                            .sum::<i32>();
-        total.to_string()
-    }
-}
\ No newline at end of file
+        total_winnings(&parsed_input.p2, &Accumulator::new(7, 2)).to_string()
+    }
+}
+
+fn explanations_to_text(explanations: &[HandExplanation]) -> String {
+    let mut out = format!(
+        "{:>5} | {:<5} | {:<12} | {:<5} | {:>5} | {:>9} | {:<22} | {}\n",
+        "rank", "hand", "type", "as", "bid", "winnings", "vs below", "vs above"
+    );
+    for explanation in explanations {
+        out.push_str(&format!(
+            "{:>5} | {:<5} | {:<12} | {:<5} | {:>5} | {:>9} | {:<22} | {}\n",
+            explanation.rank,
+            explanation.cards,
+            format!("{:?}", explanation.hand_type),
+            explanation.substituted,
+            explanation.bid,
+            explanation.winnings,
+            explanation.below.as_deref().unwrap_or("-"),
+            explanation.above.as_deref().unwrap_or("-")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checked::Accumulator;
+    use crate::day07::{
+        evaluate, hand_type_distribution, CamelCards, Card, Day07, Draw, Hand, HandExplanation,
+        HandType, PlayingCard, Poker, Rules,
+    };
+    use crate::Solution;
+
+    const EXAMPLE: &str = "32T3K 765\nT55J5 684\nKK677 28\nKTJJT 220\nQQQJA 483";
+
+    #[test]
+    fn validate_example() {
+        let (p1, p2) = Day07::solve_parsed(Day07::parse_input(EXAMPLE));
+        assert_eq!((p1.as_str(), p2.as_str()), ("6440", "5905"));
+    }
+
+    #[test]
+    fn validate_explanation() {
+        let input = Day07::parse_input(EXAMPLE);
+        let explanations = Day07::explain(&input.p2, &Accumulator::new(7, 2));
+        assert_eq!(
+            explanations.iter().map(|e| e.cards.as_str()).collect::<Vec<_>>(),
+            vec!["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"]
+        );
+        assert_eq!(
+            explanations[4],
+            HandExplanation {
+                rank: 5,
+                cards: "KTJJT".to_string(),
+                hand_type: HandType::FourOfAKind,
+                substituted: "KTTTT".to_string(),
+                bid: 220,
+                winnings: 1100,
+                below: Some("card 1: Q < K".to_string()),
+                above: None,
+            }
+        );
+        assert_eq!(explanations[1].above.as_deref(), Some("TwoPair < FourOfAKind"));
+        assert_eq!(explanations.iter().map(|e| e.winnings).sum::<i64>(), 5905);
+    }
+
+    // Same cards as Camel Cards, but with three card hands
+    struct ThreeCards;
+    impl Rules for ThreeCards {
+        type Card = Card;
+
+        fn hand_size(&self) -> usize {
+            3
+        }
+
+        fn parse_cards(&self, input: &str) -> Option<Vec<Card>> {
+            CamelCards::with_jokers().parse_cards(input)
+        }
+
+        fn is_wild(&self, card: &Card) -> bool {
+            CamelCards::with_jokers().is_wild(card)
+        }
+
+        fn substitutes(&self) -> Vec<Card> {
+            CamelCards::with_jokers().substitutes()
+        }
+
+        fn strength(&self, card: &Card) -> u8 {
+            CamelCards::with_jokers().strength(card)
+        }
+
+        fn ladder(&self) -> &[HandType] {
+            &[HandType::HighCard, HandType::OnePair, HandType::ThreeOfAKind]
+        }
+
+        fn natural_type(&self, cards: &[Card]) -> HandType {
+            CamelCards::with_jokers().natural_type(cards)
+        }
+    }
+
+    #[test]
+    fn validate_wildcard_evaluation() {
+        let jokers = CamelCards::with_jokers();
+        let cards = jokers.parse_cards("JJJJJ").unwrap();
+        assert_eq!(evaluate(&jokers, &cards).hand_type, HandType::FiveOfAKind);
+        let cards = jokers.parse_cards("2J3J4").unwrap();
+        assert_eq!(evaluate(&jokers, &cards).hand_type, HandType::ThreeOfAKind);
+
+        let cards = ThreeCards.parse_cards("K2J").unwrap();
+        let evaluation = evaluate(&ThreeCards, &cards);
+        assert_eq!(evaluation.hand_type, HandType::OnePair);
+        assert_eq!(evaluation.substituted, ThreeCards.parse_cards("K2K").unwrap());
+        let cards = ThreeCards.parse_cards("JJ9").unwrap();
+        assert_eq!(evaluate(&ThreeCards, &cards).hand_type, HandType::ThreeOfAKind);
+    }
+
+    fn poker(cards: &str) -> Hand<PlayingCard> {
+        Hand::parse(&Poker, &format!("{} 1", cards))
+    }
+
+    #[test]
+    fn validate_poker_categories() {
+        use HandType::*;
+        let hands = [
+            ("2C 5D 9H JS KC", HighCard),
+            ("2C 2D 9H JS KC", OnePair),
+            ("2C 2D 9H 9S KC", TwoPair),
+            ("2C 2D 2H 9S KC", ThreeOfAKind),
+            ("AC 2D 3H 4S 5C", Straight),
+            ("2H 7H 9H JH KH", Flush),
+            ("2C 2D 2H 9S 9C", FullHouse),
+            ("2C 2D 2H 2S KC", FourOfAKind),
+            ("5S 6S 7S 8S 9S", StraightFlush),
+            ("10D JD QD KD AD", RoyalFlush),
+        ];
+        let ranked = hands.iter().map(|(cards, _)| poker(cards)).collect::<Vec<_>>();
+        for ((cards, hand_type), hand) in hands.iter().zip(ranked.iter()) {
+            assert_eq!(hand.hand_type(), *hand_type, "{}", cards);
+        }
+        assert!(ranked.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn validate_poker_tie_breaks() {
+        // Higher pair wins regardless of kickers, then kickers in order
+        assert!(poker("3C 3D AH KS QC") < poker("4C 4D 2H 5S 6C"));
+        assert!(poker("9C 9D AH KS 2C") < poker("9H 9S AD KC 3H"));
+        assert!(poker("TC TD 4H 4S 2C") < poker("TH TS 5D 5C 2D"));
+        // A-low is the lowest straight, and suits never break ties
+        assert!(poker("AC 2D 3H 4S 5C") < poker("2H 3D 4C 5S 6H"));
+        assert_eq!(poker("AH KH 9H 5H 2H").cmp(&poker("AS KS 9S 5S 2S")), std::cmp::Ordering::Equal);
+        assert_eq!(poker("AH KH 9H 5H 2H"), poker("AS KS 9S 5S 2S"));
+        assert_eq!(Poker.parse_cards("AS 1S"), None);
+        assert_eq!(Poker.parse_cards("AS AS AS AS AS"), None);
+        assert_eq!(Poker.parse_cards("AS AD AC AH AS"), None);
+    }
+
+    #[test]
+    fn validate_distributions() {
+        use HandType::*;
+        let deck = Draw::WithoutReplacement { copies: 4 };
+        let dealt = hand_type_distribution(&CamelCards::standard(), deck, &[]);
+        assert_eq!(dealt.total, 2598960);
+        assert_eq!(
+            [HighCard, OnePair, TwoPair, ThreeOfAKind, FullHouse, FourOfAKind, FiveOfAKind].map(|t| dealt.count(t)),
+            [1317888, 1098240, 123552, 54912, 3744, 624, 0]
+        );
+
+        let drawn = hand_type_distribution(&CamelCards::standard(), Draw::WithReplacement, &[]);
+        assert_eq!((drawn.total, drawn.count(FiveOfAKind)), (371293, 13));
+        assert_eq!(drawn.counts.values().sum::<u128>(), drawn.total);
+
+        // Five of a kind is any single rank plus jokers
+        let jokers = hand_type_distribution(&CamelCards::with_jokers(), Draw::WithReplacement, &[]);
+        assert_eq!(jokers.count(FiveOfAKind), 1 + 12 * (1 + 5 + 10 + 10 + 5));
+
+        let given = CamelCards::standard().parse_cards("AAA").unwrap();
+        let aces = hand_type_distribution(&CamelCards::standard(), deck, &given);
+        assert_eq!((aces.total, aces.count(FourOfAKind), aces.count(FullHouse)), (1176, 48, 72));
+        let given = CamelCards::with_jokers().parse_cards("JJJJ").unwrap();
+        let wild = hand_type_distribution(&CamelCards::with_jokers(), Draw::WithReplacement, &given);
+        assert_eq!(wild.count(FiveOfAKind), wild.total);
+    }
+}
@@ -1,32 +1,144 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::Solution;
+use itertools::Itertools;
 
-#[derive(Debug)]
-pub struct Node {
-    left: String,
-    right: String
-}
+use crate::checked::Accumulator;
+use crate::report::{ExplainError, OutputFormat};
+use crate::Solution;
 
+// Node names are interned into dense ids as they're seen, the network is
+// then just the left and right child of every id.
 #[derive(Debug, Default)]
-pub struct Map(HashMap<String, Node>);
+pub struct Map {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
 impl Map {
+    const UNDEFINED: usize = usize::MAX;
+
     fn add(&mut self, input: &str) {
         let split_input = input.split('=').collect::<Vec<_>>();
 
-        let name = split_input[0].trim().to_string();
+        let name = split_input[0].trim();
         let map = split_input[1]
                                     .trim()
                                     .split(',')
                                     .map(|s| s.trim().trim_start_matches('(').trim_end_matches(')'))
                                     .collect::<Vec<_>>();
         assert_eq!(map.len(), 2);
-        let node = Node {
-            left: map[0].to_string(),
-            right: map[1].to_string()
+        let id = self.intern(name);
+        self.left[id] = self.intern(map[0]);
+        self.right[id] = self.intern(map[1]);
+    }
+
+    fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.left.push(Map::UNDEFINED);
+        self.right.push(Map::UNDEFINED);
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    // Ids of every node whose name matches, in the order they were seen
+    pub fn ids_where<F>(&self, f: F) -> Vec<usize>
+    where
+        F: Fn(&str) -> bool,
+    {
+        (0..self.len()).filter(|&id| f(self.name(id))).collect()
+    }
+
+    // Every node that can be reached from `start`, including itself
+    pub fn reachable_from(&self, start: usize) -> Vec<usize> {
+        let mut seen = vec![false; self.len()];
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+        while let Some(id) = queue.pop_front() {
+            for next in [self.left[id], self.right[id]] {
+                if next != Map::UNDEFINED && !seen[next] {
+                    seen[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        (0..self.len()).filter(|&id| seen[id]).collect()
+    }
+
+    // A Graphviz digraph of `nodes` with L/R labelled edges, start (..A) and
+    // end (..Z) nodes filled in, and the edges walked along `path` in blue.
+    pub fn to_dot(&self, graph_name: &str, nodes: &[usize], path: &[usize]) -> String {
+        let path_edges = path.windows(2).map(|step| (step[0], step[1])).collect::<HashSet<_>>();
+        let mut out = format!("digraph {:?} {{\n    node [shape=circle];\n", graph_name);
+
+        for &id in nodes {
+            let name = self.name(id);
+            let mut attributes = vec![];
+            if name.ends_with('A') {
+                attributes.push("style=filled, fillcolor=palegreen");
+            } else if name.ends_with('Z') {
+                attributes.push("style=filled, fillcolor=lightcoral");
+            }
+            if path.contains(&id) {
+                attributes.push("color=blue");
+            }
+            if !attributes.is_empty() {
+                out.push_str(&format!("    {:?} [{}];\n", name, attributes.join(", ")));
+            }
+        }
+
+        for &id in nodes {
+            let edges = if self.left[id] == self.right[id] {
+                vec![("L/R", self.left[id])]
+            } else {
+                vec![("L", self.left[id]), ("R", self.right[id])]
+            };
+            for (label, next) in edges.into_iter().filter(|&(_, next)| next != Map::UNDEFINED) {
+                let highlight = if path_edges.contains(&(id, next)) { ", color=blue, penwidth=2" } else { "" };
+                out.push_str(&format!(
+                    "    {:?} -> {:?} [label={:?}{}];\n",
+                    self.name(id),
+                    self.name(next),
+                    label,
+                    highlight
+                ));
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn next(&self, id: usize, instruction: char) -> usize {
+        let next = match instruction {
+            'L' => self.left[id],
+            'R' => self.right[id],
+            _ => panic!("Invalid instruction {:?}", instruction),
         };
-        self.0.insert(name.trim().to_string(), node);
-        return;
+        if next == Map::UNDEFINED {
+            panic!("Node {} is never defined", self.name(id));
+        }
+        next
     }
 }
 #[derive(Debug)]
@@ -35,32 +147,193 @@ pub struct Input {
     map: Map
 }
 impl Input {
-    fn traverse_until_end<F>(&self, start: &String, end: F) -> usize 
+    fn traverse_until_end<F>(&self, start: usize, end: F) -> usize 
     where  
-        F: Fn(&String) -> bool,
+        F: Fn(usize) -> bool,
     {
         let mut curr = start;
         let mut steps = 0;
 
         while !end(curr) {
             let index = steps % self.instructions.len();
-            let instruction = self.instructions[index];
+            curr = self.map.next(curr, self.instructions[index]);
+            steps += 1;
+        }
 
-            let branch = self.map.0.get(curr).unwrap();
-            curr = match instruction {
-                'L' => &branch.left,
-                'R' => &branch.right,
-                _ => panic!("Error {:?}", self),
-            };
+        steps
+    }
 
+    // The nodes `traverse_until_end` passes through, start and end included.
+    // Stops early if the walk starts repeating without reaching an end.
+    pub fn path_until_end<F>(&self, start: usize, end: F) -> Vec<usize>
+    where
+        F: Fn(usize) -> bool,
+    {
+        let mut path = vec![start];
+        let mut curr = start;
+        let mut steps = 0;
+
+        while !end(curr) && steps < self.map.len() * self.instructions.len() {
+            let index = steps % self.instructions.len();
+            curr = self.map.next(curr, self.instructions[index]);
+            path.push(curr);
             steps += 1;
         }
 
-        steps
+        path
     }
+
+    // Walks from `start` until a (node, instruction index) state repeats,
+    // after that the walk goes round the same cycle forever.
+    fn find_cycle<F>(&self, start: usize, end: F) -> Cycle
+    where
+        F: Fn(usize) -> bool,
+    {
+        let states = self.instructions.len();
+        let mut seen = vec![usize::MAX; self.map.len() * states];
+        let mut hits = vec![];
+        let mut curr = start;
+        let mut steps = 0;
+
+        loop {
+            let index = steps % states;
+            let first_seen = seen[curr * states + index];
+            if first_seen != usize::MAX {
+                let (prefix_hits, cycle_hits) = hits.into_iter().partition(|&hit| hit < first_seen);
+                return Cycle {
+                    prefix: first_seen,
+                    length: steps - first_seen,
+                    prefix_hits,
+                    cycle_hits,
+                };
+            }
+            seen[curr * states + index] = steps;
+            if end(curr) {
+                hits.push(steps);
+            }
+
+            curr = self.map.next(curr, self.instructions[index]);
+            steps += 1;
+        }
+    }
+}
+
+// One ghost's walk: `prefix` steps before it enters a cycle of `length`
+// steps. End nodes are reached at each of `prefix_hits` once and at each of
+// `cycle_hits` plus any multiple of `length`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub prefix: usize,
+    pub length: usize,
+    pub prefix_hits: Vec<usize>,
+    pub cycle_hits: Vec<usize>,
+}
+impl Cycle {
+    pub fn is_hit(&self, steps: usize) -> bool {
+        if steps < self.prefix {
+            self.prefix_hits.contains(&steps)
+        } else {
+            self.cycle_hits.contains(&(self.prefix + (steps - self.prefix) % self.length))
+        }
+    }
+
+    pub fn first_hit(&self) -> Option<usize> {
+        self.prefix_hits.first().or(self.cycle_hits.first()).copied()
+    }
+
+    fn hits_below(&self, limit: usize) -> Vec<usize> {
+        let mut hits = self.prefix_hits.clone();
+        for &hit in self.cycle_hits.iter() {
+            hits.extend((hit..limit).step_by(self.length));
+        }
+        hits.sort();
+        hits
+    }
+}
+
+// The first step count at which every ghost is on an end node, if there is
+// one. Before every ghost is in its cycle the candidates are checked one by
+// one, after that each combination of cycle hits is a set of congruences.
+pub fn first_common_hit(cycles: &[Cycle], acc: &Accumulator) -> Option<usize> {
+    let settled = cycles.iter().map(|cycle| cycle.prefix).max()?;
+    if let Some(steps) = cycles[0]
+        .hits_below(settled)
+        .into_iter()
+        .find(|&steps| cycles.iter().all(|cycle| cycle.is_hit(steps)))
+    {
+        return Some(steps);
+    }
+
+    let mut congruences = vec![(0_i128, 1_i128)];
+    for cycle in cycles {
+        let length = cycle.length as i128;
+        congruences = congruences
+            .iter()
+            .flat_map(|&congruence| {
+                cycle
+                    .cycle_hits
+                    .iter()
+                    .filter_map(move |&hit| crt(congruence, (hit as i128 % length, length)))
+            })
+            .sorted()
+            .dedup()
+            .collect();
+    }
+
+    let settled = settled as i128;
+    congruences
+        .into_iter()
+        .map(|(residue, modulus)| residue + ((settled - residue).max(0) + modulus - 1) / modulus * modulus)
+        .min()
+        .map(|steps| acc.widen::<i128, usize>(steps))
+}
+
+// Combines x = a mod m and x = b mod n into one congruence, the moduli don't
+// need to be coprime.
+fn crt((a, m): (i128, i128), (b, n): (i128, i128)) -> Option<(i128, i128)> {
+    let (g, p, _) = extended_gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let lcm = m / g * n;
+    let x = a + m * ((b - a) / g * p % (n / g));
+    Some((x.rem_euclid(lcm), lcm))
+}
+
+// (g, x, y) with a * x + b * y = g
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - a / b * y)
 }
+
 #[derive(Debug)]
 pub struct Day08;
+impl Day08 {
+    // One graph per ghost of the nodes it can reach, with its path to the
+    // first end node drawn on top.
+    pub fn ghost_dots(input: &Input) -> Vec<String> {
+        let map = &input.map;
+        map.ids_where(|name| name.ends_with('A'))
+            .into_iter()
+            .map(|start| {
+                let path = input.path_until_end(start, |id| map.name(id).ends_with('Z'));
+                map.to_dot(&format!("ghost_{}", map.name(start)), &map.reachable_from(start), &path)
+            })
+            .collect()
+    }
+
+    // Text is the Graphviz DOT source
+    pub fn print_explanation(input_lines: &str, format: OutputFormat) -> Result<(), ExplainError> {
+        if format != OutputFormat::Text {
+            return Err(ExplainError::UnsupportedFormat { day: 8, format });
+        }
+        print!("{}", Day08::ghost_dots(&Day08::parse_input(input_lines)).concat());
+        Ok(())
+    }
+}
 impl Solution for Day08 {
     type ParsedInput = Input;
 
@@ -76,39 +349,46 @@ impl Solution for Day08 {
     }
 
     fn part_one(parsed_input: &mut Self::ParsedInput) -> String {
-        let steps = parsed_input.traverse_until_end(&"AAA".to_string(), |s| s == "ZZZ");
+        let map = &parsed_input.map;
+        let start = map.id("AAA").expect("No node AAA");
+        let end = map.id("ZZZ").expect("No node ZZZ");
+        let steps = parsed_input.traverse_until_end(start, |id| id == end);
         steps.to_string()
     }
 
     fn part_two(parsed_input: &mut Self::ParsedInput) -> String {
-        let starts = parsed_input.map.0
-                                        .keys()
-                                        .filter(|k| k.ends_with('A'))
-                                        .collect::<Vec<_>>();
-        let ends = parsed_input.map.0
-                                        .keys()
-                                        .filter(|k| k.ends_with('Z'))
-                                        .collect::<Vec<_>>();
-                                    
-        let mut steps = vec![];
-        for start in starts {
-            let step = parsed_input.traverse_until_end(start, |s| ends.contains(&&s));
-            steps.push(step);
+        let map = &parsed_input.map;
+        let starts = map.ids_where(|name| name.ends_with('A'));
+        let is_end = (0..map.len()).map(|id| map.name(id).ends_with('Z')).collect::<Vec<_>>();
+
+        let cycles = starts
+            .iter()
+            .map(|&start| parsed_input.find_cycle(start, |id| is_end[id]))
+            .collect::<Vec<_>>();
+        let total = first_common_hit(&cycles, &Accumulator::new(8, 2)).expect("The ghosts never all reach an end node together");
+
+        // Taking the lcm of the first hits only works when every ghost
+        // reaches an end node exactly once per cycle, at a multiple of it
+        let first_hits = cycles.iter().map(|cycle| cycle.first_hit().unwrap()).collect::<Vec<_>>();
+        // An lcm too big for a usize can't be the answer either
+        match lcm(&first_hits) {
+            Some(shortcut) if shortcut == total => {}
+            Some(shortcut) => eprintln!("Warning: the lcm of the first hits is {} but the ghosts first meet at {}", shortcut, total),
+            None => eprintln!("Warning: the lcm of the first hits overflows but the ghosts first meet at {}", total),
         }
-        let total = lcm(&steps);
         total.to_string()
     }
 }
 
 // https://www.youtube.com/watch?v=t5ktQvHJG2Y
 // https://github.com/TheAlgorithms/Rust/blob/master/src/math/lcm_of_n_numbers.rs
-pub fn lcm(nums: &[usize]) -> usize {
+pub fn lcm(nums: &[usize]) -> Option<usize> {
     if nums.len() == 1 {
-        return nums[0];
+        return Some(nums[0]);
     }
     let a = nums[0];
-    let b = lcm(&nums[1..]);
-    a * b / gcd_of_two_numbers(a, b)
+    let b = lcm(&nums[1..])?;
+    (a / gcd_of_two_numbers(a, b)).checked_mul(b)
 }
 
 fn gcd_of_two_numbers(a: usize, b: usize) -> usize {
@@ -116,4 +396,79 @@ fn gcd_of_two_numbers(a: usize, b: usize) -> usize {
         return a;
     }
     gcd_of_two_numbers(b, a % b)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checked::Accumulator;
+    use crate::day08::{first_common_hit, lcm, Cycle, Day08};
+    use crate::Solution;
+
+    const GHOSTS: &str = "LR\n\n11A = (11B, XXX)\n11B = (XXX, 11Z)\n11Z = (11B, XXX)\n22A = (22B, XXX)\n22B = (22C, 22C)\n22C = (22Z, 22Z)\n22Z = (22B, 22B)\nXXX = (XXX, XXX)";
+
+    #[test]
+    fn validate_cycles() {
+        let input = Day08::parse_input(GHOSTS);
+        let cycle = input.find_cycle(input.map.id("22A").unwrap(), |id| input.map.name(id).ends_with('Z'));
+        assert_eq!(
+            cycle,
+            Cycle { prefix: 1, length: 6, prefix_hits: vec![], cycle_hits: vec![3, 6] }
+        );
+        assert_eq!(Day08::part_two(&mut Day08::parse_input(GHOSTS)), "6");
+
+        let mut input = Day08::parse_input("LLR\n\nAAA = (BBB, BBB)\nBBB = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)");
+        assert_eq!(Day08::part_one(&mut input), "6");
+    }
+
+    #[test]
+    fn validate_without_lcm_shortcut() {
+        let acc = Accumulator::new(8, 2);
+        // Odd steps and steps that are 2 mod 3, the lcm of the first hits is 2
+        let odd = Cycle { prefix: 1, length: 2, prefix_hits: vec![], cycle_hits: vec![1] };
+        let offset = Cycle { prefix: 1, length: 3, prefix_hits: vec![], cycle_hits: vec![2] };
+        assert_eq!(first_common_hit(&[odd.clone(), offset.clone()], &acc), Some(5));
+
+        // Hits before the cycle starts count too
+        let early = Cycle { prefix: 4, length: 2, prefix_hits: vec![2], cycle_hits: vec![5] };
+        assert_eq!(first_common_hit(&[early.clone(), Cycle { prefix: 0, length: 2, prefix_hits: vec![], cycle_hits: vec![0] }], &acc), Some(2));
+        assert_eq!(first_common_hit(&[early, Cycle { prefix: 0, length: 4, prefix_hits: vec![], cycle_hits: vec![3] }], &acc), Some(7));
+
+        let even = Cycle { prefix: 0, length: 2, prefix_hits: vec![], cycle_hits: vec![0] };
+        assert_eq!(first_common_hit(&[odd, even], &acc), None);
+    }
+
+    #[test]
+    fn validate_dot_export() {
+        let input = Day08::parse_input(GHOSTS);
+        let dots = Day08::ghost_dots(&input);
+        assert_eq!(dots.len(), 2);
+        assert_eq!(
+            dots[0],
+            [
+                "digraph \"ghost_11A\" {",
+                "    node [shape=circle];",
+                "    \"11A\" [style=filled, fillcolor=palegreen, color=blue];",
+                "    \"11B\" [color=blue];",
+                "    \"11Z\" [style=filled, fillcolor=lightcoral, color=blue];",
+                "    \"11A\" -> \"11B\" [label=\"L\", color=blue, penwidth=2];",
+                "    \"11A\" -> \"XXX\" [label=\"R\"];",
+                "    \"11B\" -> \"XXX\" [label=\"L\"];",
+                "    \"11B\" -> \"11Z\" [label=\"R\", color=blue, penwidth=2];",
+                "    \"XXX\" -> \"XXX\" [label=\"L/R\"];",
+                "    \"11Z\" -> \"11B\" [label=\"L\"];",
+                "    \"11Z\" -> \"XXX\" [label=\"R\"];",
+                "}\n",
+            ]
+            .join("\n")
+        );
+        assert!(!dots[1].contains("11A"));
+    }
+
+    #[test]
+    fn validate_lcm_overflow() {
+        assert_eq!(lcm(&[4, 6, 10]), Some(60));
+        // Two big primes, each fits but their product doesn't
+        assert_eq!(lcm(&[4_294_967_311, 4_294_967_357]), None);
+        assert_eq!(lcm(&[usize::MAX, usize::MAX]), Some(usize::MAX));
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::VecDeque;
 
+use crate::checked::{Accumulator, AccumulateExt};
 use crate::Solution;
 
 pub struct History(Vec<isize>);
@@ -23,7 +24,7 @@ impl Solution for Day09 {
         let total: isize = parsed_input 
                             .iter()
                             .map(|History(vec)| find_next(vec, &mut VecDeque::from(vec![vec[vec.len() - 1]])))
-                            .sum::<isize>();
+                            .sum_with(&Accumulator::new(9, 1));
         total.to_string()
     }
 
@@ -31,7 +32,7 @@ impl Solution for Day09 {
         let total: isize = parsed_input 
                             .iter()
                             .map(|History(vec)| find_prev(vec, &mut VecDeque::from(vec![vec[0]])))
-                            .sum::<isize>();
+                            .sum_with(&Accumulator::new(9, 2));
         total.to_string()
     }
 }
@@ -1,9 +1,13 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
 
 use itertools::iproduct;
 
+use crate::checked::Accumulator;
+use crate::geometry::{interior_points, Point};
 use crate::Solution;
 
+const PIPES: [char; 6] = ['|', '-', 'L', 'J', '7', 'F'];
+
 #[derive(Clone, Debug, PartialEq, Hash, Eq, PartialOrd, Ord)]
 pub struct Coord {
     x: i32,
@@ -78,6 +82,7 @@ impl Node {
         
         connected_coords.extend(neighbors.into_iter().filter_map(|neighbor| {
             map.get(&neighbor)
+                .filter(|node| PIPES.contains(&node.data))
                 .and_then(|node| {
                     let connections = node.get_valid_connections(map);
                     if connections.contains(&self.coord) {
@@ -88,19 +93,17 @@ impl Node {
                 })
         }));
 
-        let valid_chars = vec!['|', '-', 'L', 'J', '7', 'F'];
-        let node = valid_chars
+        let node = PIPES
                     .into_iter()
                     .map(|c| Node {
                         coord: self.coord.clone(),
                         data: c,
                     })
-                    .filter(|node| {
+                    .find(|node| {
                         let mut coords = node.get_valid_connections(map).clone();
                         coords.sort();
                         coords == connected_coords
                     })
-                    .next()
                     .unwrap();
         node.data
     }
@@ -133,29 +136,24 @@ impl Solution for Day10 {
     fn part_one(map: &mut Self::ParsedInput) -> String {
         let start_node = get_start_node(map);
 
-        let visited = breadth_first_traversal(start_node, &map);
+        let visited = breadth_first_traversal(start_node, map);
         visited.values().max().unwrap().to_string()
     }
 
     fn part_two(map: &mut Self::ParsedInput) -> String {
         let start_node = get_start_node(map);
-        let traversed = breadth_first_traversal(start_node, &map);
-        let found_loop = traversed.into_keys().collect::<HashSet<Node>>();
-        let nodes_in_loop = map
-                                        .values()
-                                        .clone()
-                                        .filter(|&node| !found_loop.contains(node))
-                                        .filter(|&node| point_in_polygon(node, &found_loop, &map))
-                                        .collect::<Vec<&Node>>(); 
-        nodes_in_loop.len().to_string()
+        let vertices = trace_loop(&start_node, map)
+                            .iter()
+                            .map(|coord| Point::new(coord.x as i64, coord.y as i64))
+                            .collect::<Vec<Point>>();
+        interior_points(&vertices, &Accumulator::new(10, 2)).to_string()
     }
 }
 
 fn get_start_node(map: &HashMap<Coord, Node>) -> Node {
     map
         .values()
-        .filter(|&node| node.data == 'S')
-        .next()
+        .find(|&node| node.data == 'S')
         .unwrap()
         .clone()
 }
@@ -201,21 +199,39 @@ fn get_next_nodes(current_nodes: &[Node], map: &HashMap<Coord, Node>) -> Vec<Nod
         .collect()
 }
 
-//https://en.wikipedia.org/wiki/Point_in_polygon
-fn point_in_polygon(node: &Node, found_loop: &HashSet<Node>, map: &HashMap<Coord, Node>) -> bool {
-    let mut current_coord = node.coord.clone();
-    let mut intersections = 0;
-
-    while map.contains_key(&current_coord) {
-        let current_node = map.get(&current_coord).unwrap();
-        let data = current_node.get_node_data(map);
-     
-        if found_loop.contains(current_node) && ['|', 'J', 'L'].contains(&data) {
-            intersections += 1;
-        }
-     
-        current_coord.x += 1;
-     }
-     
-     (intersections % 2) == 1
-}
\ No newline at end of file
+// Walks the loop from the start node, returning every tile in order
+// so it can be treated as the vertices of a polygon.
+fn trace_loop(start_node: &Node, map: &HashMap<Coord, Node>) -> Vec<Coord> {
+    let mut path = vec![start_node.coord.clone()];
+    let mut previous = start_node.coord.clone();
+    let mut current = start_node.get_valid_connections(map)[0].clone();
+
+    while current != start_node.coord {
+        let node = map.get(&current).unwrap();
+        let next = node
+                    .get_valid_connections(map)
+                    .into_iter()
+                    .find(|coord| *coord != previous)
+                    .unwrap();
+        path.push(current);
+        previous = path.last().unwrap().clone();
+        current = next;
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day10::Day10;
+    use crate::Solution;
+
+    const SQUARE_LOOP: &str = ".....\n.S-7.\n.|.|.\n.L-J.\n.....";
+    const ENCLOSED: &str = "...........\n.S-------7.\n.|F-----7|.\n.||.....||.\n.||.....||.\n.|L-7.F-J|.\n.|..|.|..|.\n.L--J.L--J.\n...........";
+
+    #[test]
+    fn validate_examples() {
+        assert_eq!(Day10::solve(SQUARE_LOOP), ("4".to_string(), "1".to_string()));
+        assert_eq!(Day10::solve_part_two(ENCLOSED), "4");
+    }
+}
@@ -2,6 +2,7 @@ use std::ops::{Add, Sub};
 
 use itertools::Itertools;
 
+use crate::checked::{Accumulator, AccumulateExt};
 use crate::Solution;
 
 #[derive(Debug, Copy, Clone)]
@@ -149,10 +150,9 @@ impl Solution for Day11 {
                             let expanded_a = input.expand_galaxy_on_coord(a);
                             let expanded_b = input.expand_galaxy_on_coord(b);
                             let v = (expanded_a - expanded_b).abs();
-                            let distance = (v.x + v.y).abs();
-                            distance
+                            (v.x + v.y).abs()
                         })
-                        .sum::<i64>();
+                        .sum_with(&Accumulator::new(11, 1));
         count.to_string()
     }
 
@@ -167,10 +167,9 @@ impl Solution for Day11 {
                             let expanded_a = input.expand_galaxy_by_size_on_coord(a, expansion_size);
                             let expanded_b = input.expand_galaxy_by_size_on_coord(b, expansion_size);
                             let v = (expanded_a - expanded_b).abs();
-                            let distance = (v.x + v.y).abs();
-                            distance
+                            (v.x + v.y).abs()
                         })
-                        .sum::<i64>();
+                        .sum_with(&Accumulator::new(11, 2));
         count.to_string()
     }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-use std::collections::HashMap;
-
+use crate::checked::Accumulator;
+use crate::memo::{Memo, MemoStats};
 use crate::Solution;
 use nom::{
     bytes::complete::tag,
@@ -65,10 +65,28 @@ impl Record {
                         .collect();
         Record::new(springs, groups)
     }
+
+    pub fn count_arrangements(&self) -> (usize, MemoStats) {
+        let mut memo = Memo::new();
+        let solutions = find_possible_solutions(&mut memo, self, 0, 0);
+        (solutions, memo.stats())
+    }
 }
 
 #[derive(Debug)]
 pub struct Day12;
+impl Day12 {
+    // Arrangements over all the records, along with how well the memo did
+    // across all of them.
+    pub fn count_all(records: impl IntoIterator<Item = Record>, acc: &Accumulator) -> (usize, MemoStats) {
+        records
+            .into_iter()
+            .map(|record| record.count_arrangements())
+            .fold((0, MemoStats::default()), |(total, stats), (solutions, record_stats)| {
+                (acc.add(total, solutions), stats + record_stats)
+            })
+    }
+}
 impl Solution for Day12 {
     type ParsedInput = Vec<Record>;
 
@@ -80,86 +98,101 @@ impl Solution for Day12 {
     }
 
     fn part_one(records: &mut Self::ParsedInput) -> String {
-        let mut cache = HashMap::new();
-        let solutions = records
-            .iter()
-            .map(|r| find_possible_solutions(&mut cache, r))
-            .sum::<usize>();
+        let (solutions, _) = Day12::count_all(records.iter().cloned(), &Accumulator::new(12, 1));
         solutions.to_string()
     }
 
     fn part_two(records: &mut Self::ParsedInput) -> String {
-        let mut cache = HashMap::new();
-        let solutions = records
-            .iter()
-            .map(|r| find_possible_solutions(&mut cache, &r.expand_by(5)))
-            .sum::<usize>();
+        let expanded = records.iter().map(|r| r.expand_by(5));
+        let (solutions, _) = Day12::count_all(expanded, &Accumulator::new(12, 2));
         solutions.to_string()
     }
 }
 
-// DP, Recusrive with memo/cache to keep track of previously found solutions
+// DP, Recusrive with memo/cache to keep track of previously found solutions.
+// The memo is keyed on (spring index, group index) into the record, so it is
+// only valid for a single record.
 // S# are the sentinel cases
-fn find_possible_solutions(cache: &mut HashMap<Record, usize>, record: &Record) -> usize {
+fn find_possible_solutions(
+    memo: &mut Memo<(usize, usize), usize>,
+    record: &Record,
+    spring_idx: usize,
+    group_idx: usize,
+) -> usize {
     use SpringType::*;
-    // S1: we have already found a possible solution for the record
-    if let Some(&v) = cache.get(record) {
-        return v;
-    }
+    // S1: we have already found a possible solution for the record (handled by the memo)
+    memo.get_or_compute((spring_idx, group_idx), |memo| {
+        let springs = &record.springs[spring_idx..];
+        let groups = &record.groups[group_idx..];
+
+        // S2: no groups left, there are no other damaged springs otherwise it cannot be valid
+        if groups.is_empty() {
+            return match springs.contains(&Damaged) {
+                true => 0,
+                false => 1,
+            };
+        }
 
-    // S2: no groups left, there are no other damaged springs otherwise it cannot be valid
-    if record.groups.is_empty() {
-        let v = match record.springs.iter().any(|c| *c == Damaged) {
-            true => 0,
-            false => 1,
-        };
-        cache.insert(record.clone(), v);
-        return v;
-    }
+        // S3: we have some groups left so ensure we have enough springs to fill them
+        if springs.len() < groups.iter().sum::<usize>() + groups.len() - 1 {
+            return 0;
+        }
 
-    // S3: we have some groups left so ensure we have enough springs to fill them
-    if record.springs.len() < record.groups.iter().sum::<usize>() + record.groups.len() - 1 {
-        cache.insert(record.clone(), 0);
-        return 0;
-    }
+        // S4: we cannot work with operational springs i.e. c == '.' so skip
+        if springs[0] == Operational {
+            return find_possible_solutions(memo, record, spring_idx + 1, group_idx);
+        }
 
-    // S4: we cannot work with operational springs i.e. c == '.' so skip
-    if record.springs[0] == Operational{
-        let solutions = find_possible_solutions(
-            cache,
-            &Record::new(record.springs[1..].to_vec(), record.groups.clone()),
-        );
-        cache.insert(record.clone(), solutions);
-        return solutions;
-    } 
-
-    // Here, we know we are at the beginning of a possible position for the current group. 
-    // Check if that's possible and if it is, we find many valid solutions we'd get if we did.
-    let mut solutions = 0;
-    let cur = record.groups[0];
-    let non_operational = record.springs[0..cur]
-        .iter()
-        .all(|c| *c != Operational);
-    let end = (cur + 1).min(record.springs.len());
-    if non_operational
-        && ((record.springs.len() > cur && record.springs[cur] != Damaged)
-            || record.springs.len() <= cur)
-    {
-        solutions = find_possible_solutions(
-            cache,
-            &Record::new(record.springs[end..].to_vec(), record.groups[1..].to_vec()),
-        );
-    }
+        // Here, we know we are at the beginning of a possible position for the current group.
+        // Check if that's possible and if it is, we find many valid solutions we'd get if we did.
+        let mut solutions = 0;
+        let cur = groups[0];
+        let non_operational = springs[0..cur]
+            .iter()
+            .all(|c| *c != Operational);
+        let end = (cur + 1).min(springs.len());
+        if non_operational
+            && ((springs.len() > cur && springs[cur] != Damaged)
+                || springs.len() <= cur)
+        {
+            solutions = find_possible_solutions(memo, record, spring_idx + end, group_idx + 1);
+        }
 
-    // If current position is Unknown, we can choose not to use that solution
-    if record.springs[0] == Unknown {
-        solutions += find_possible_solutions(
-            cache,
-            &Record::new(record.springs[1..].to_vec(), record.groups.clone()),
-        );
-    }
+        // If current position is Unknown, we can choose not to use that solution
+        if springs[0] == Unknown {
+            solutions += find_possible_solutions(memo, record, spring_idx + 1, group_idx);
+        }
+
+        // We have the number of solutions for this record, the memo caches it
+        solutions
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checked::Accumulator;
+    use crate::day12::Day12;
+    use crate::memo::MemoStats;
+    use crate::Solution;
 
-    // We have the number of solutions for this record, so cache and return it
-    cache.insert(record.clone(), solutions);
-    solutions
-}
\ No newline at end of file
+    const EXAMPLE: &str = "???.### 1,1,3\n.??..??...?##. 1,1,3\n?#?#?#?#?#?#?#? 1,3,1,6\n????.#...#... 4,1,1\n????.######..#####. 1,6,5\n?###???????? 3,2,1";
+
+    #[test]
+    fn validate_memo_stats_are_summed() {
+        let records = Day12::parse_input(EXAMPLE);
+        let acc = Accumulator::new(12, 2);
+        let expanded = records.iter().map(|r| r.expand_by(5)).collect::<Vec<_>>();
+
+        let (solutions, stats) = Day12::count_all(expanded.clone(), &acc);
+        assert_eq!(solutions, 525152);
+        let per_record = expanded
+            .iter()
+            .map(|record| record.count_arrangements().1)
+            .fold(MemoStats::default(), |total, stats| total + stats);
+        assert_eq!(stats, per_record);
+        assert!(stats.hits > 0 && stats.hit_rate() > 0.0);
+
+        let (solutions, _) = Day12::count_all(records, &acc);
+        assert_eq!(solutions, 21);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::checked::{Accumulator, AccumulateExt};
 use crate::Solution;
 
 #[derive(Debug)]
@@ -120,10 +121,10 @@ impl Solution for Day13 {
     }
 
     fn part_one(notes: &mut Self::ParsedInput) -> String {
-        notes.iter().map(|note| note.find_reflections_p1()).sum::<usize>().to_string()
+        notes.iter().map(|note| note.find_reflections_p1()).sum_with(&Accumulator::new(13, 1)).to_string()
     }
 
     fn part_two(notes: &mut Self::ParsedInput) -> String {
-        notes.iter().map(|note| note.find_reflections_p2()).sum::<usize>().to_string()
+        notes.iter().map(|note| note.find_reflections_p2()).sum_with(&Accumulator::new(13, 2)).to_string()
     }
 }
@@ -1,4 +1,5 @@
 use std::{collections::HashSet, hash::Hasher};
+use crate::checked::{Accumulator, AccumulateExt};
 use crate::Solution;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -16,14 +17,14 @@ impl Board {
 
     // Walks through the grid looking for 'O'
     // its total is number of rows minus the row it's on.
-    fn calculate_total(&self) -> usize {
+    fn calculate_total(&self, acc: &Accumulator) -> usize {
         self.grid
             .iter()
             .enumerate()
             .flat_map(|(y, row)| {
-                row.iter().enumerate().map(move |(_, c)| if *c == 'O' { self.grid.len() - y } else { 0 })
+                row.iter().map(move |c| if *c == 'O' { self.grid.len() - y } else { 0 })
             })
-            .sum()
+            .sum_with(acc)
     }
 
     fn roll_north(&mut self) -> usize{
@@ -167,7 +168,8 @@ impl Solution for Day14 {
     }
 
     fn part_one(board: &mut Self::ParsedInput) -> String {
-        board.roll_north().to_string()
+        board.roll_north();
+        board.calculate_total(&Accumulator::new(14, 1)).to_string()
     }
 
     fn part_two(board: &mut Self::ParsedInput) -> String {
@@ -195,7 +197,7 @@ fn p2_helper(board: &mut Board) -> usize {
                 board.roll_all_without_total();
             }
 
-            return board.calculate_total();
+            return board.calculate_total(&Accumulator::new(14, 2));
         }
     }
 
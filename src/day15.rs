@@ -1,4 +1,5 @@
 use regex::Regex;
+use crate::checked::{Accumulator, AccumulateExt};
 use crate::Solution;
 
 #[derive(Debug)]
@@ -29,7 +30,7 @@ impl Step {
     }
 
     fn add_lense(&self, mut boxes: Vec<Vec<Lense>>) -> Vec<Vec<Lense>> {
-        let box_idx = hash(&self.label) as usize;
+        let box_idx = hash(&self.label);
 
         let new_lense = Lense {
             label: self.label.clone(),
@@ -50,7 +51,7 @@ impl Step {
     }
 
     fn remove_lense(&self, mut boxes: Vec<Vec<Lense>>) -> Vec<Vec<Lense>> {
-        let box_num = hash(&self.label) as usize;
+        let box_num = hash(&self.label);
 
         boxes[box_num] = boxes[box_num]
             .clone()
@@ -101,7 +102,7 @@ impl Solution for Day15 {
         parsed_input
             .iter()
             .map(|step| hash(&step.raw_string()))
-            .sum::<usize>()
+            .sum_with(&Accumulator::new(15, 1))
             .to_string()
     }
 
@@ -110,11 +111,15 @@ impl Solution for Day15 {
         for step in steps {
             boxes = step.apply_operation(boxes);
         }
+        let acc = Accumulator::new(15, 2);
         let mut power = 0;
 
         for (box_num, my_box) in boxes.iter().enumerate() {
             for (lense_index, lense) in my_box.iter().enumerate() {
-                power += (1 + box_num) * (1 + lense_index) * lense.focal_length;
+                let lense_power = [1 + box_num, 1 + lense_index, lense.focal_length]
+                    .into_iter()
+                    .product_with(&acc);
+                power = acc.add(power, lense_power);
             }
         }
 
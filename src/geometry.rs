@@ -0,0 +1,177 @@
+// Helpers for simple polygons with integer (lattice) vertices.
+// Vertices are expected in order (clockwise or anti-clockwise), the
+// closing edge from the last vertex back to the first is implied.
+// The sums over the edges go through the caller's Accumulator so overflows
+// are reported against their day.
+
+use crate::checked::{Accumulator, AccumulateExt};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+impl Point {
+    pub fn new(x: i64, y: i64) -> Point {
+        Point { x, y }
+    }
+}
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Self {
+        Point::new(x, y)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Location {
+    Inside,
+    Outside,
+    Boundary,
+}
+
+fn edges(vertices: &[Point]) -> impl Iterator<Item = (&Point, &Point)> {
+    vertices.iter().zip(vertices.iter().cycle().skip(1))
+}
+
+// https://en.wikipedia.org/wiki/Shoelace_formula
+// Twice the area is always an integer for lattice polygons, so keep it exact.
+pub fn twice_area(vertices: &[Point], acc: &Accumulator) -> i64 {
+    edges(vertices)
+        .map(|(a, b)| acc.add(acc.mul(a.x, b.y), acc.mul(-b.x, a.y)))
+        .sum_with(acc)
+        .abs()
+}
+
+pub fn area(vertices: &[Point], acc: &Accumulator) -> f64 {
+    twice_area(vertices, acc) as f64 / 2.0
+}
+
+// Lattice points on the boundary, each edge contributes gcd(|dx|, |dy|).
+pub fn boundary_points(vertices: &[Point], acc: &Accumulator) -> i64 {
+    edges(vertices)
+        .map(|(a, b)| gcd((b.x - a.x).abs(), (b.y - a.y).abs()))
+        .sum_with(acc)
+}
+
+// https://en.wikipedia.org/wiki/Pick%27s_theorem
+// A = i + b/2 - 1  =>  i = (2A - b + 2) / 2
+pub fn interior_points(vertices: &[Point], acc: &Accumulator) -> i64 {
+    (twice_area(vertices, acc) - boundary_points(vertices, acc) + 2) / 2
+}
+
+// https://en.wikipedia.org/wiki/Point_in_polygon (winding number)
+// Uses only integer cross products so points on an edge are detected exactly.
+pub fn point_in_polygon(point: &Point, vertices: &[Point]) -> Location {
+    let mut winding = 0;
+
+    for (a, b) in edges(vertices) {
+        let cross = (b.x - a.x) * (point.y - a.y) - (point.x - a.x) * (b.y - a.y);
+        let within_x = a.x.min(b.x) <= point.x && point.x <= a.x.max(b.x);
+        let within_y = a.y.min(b.y) <= point.y && point.y <= a.y.max(b.y);
+        if cross == 0 && within_x && within_y {
+            return Location::Boundary;
+        }
+
+        if a.y <= point.y {
+            if b.y > point.y && cross > 0 {
+                winding += 1;
+            }
+        } else if b.y <= point.y && cross < 0 {
+            winding -= 1;
+        }
+    }
+
+    match winding {
+        0 => Location::Outside,
+        _ => Location::Inside,
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        return a;
+    }
+    gcd(b, a % b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::checked::Accumulator;
+    use crate::geometry::{boundary_points, interior_points, point_in_polygon, twice_area, Location, Point};
+
+    fn square() -> Vec<Point> {
+        [(0, 0), (4, 0), (4, 4), (0, 4)].into_iter().map(Point::from).collect()
+    }
+
+    // A 4x4 square with the top right 2x2 corner cut out.
+    fn l_shape() -> Vec<Point> {
+        [(0, 0), (4, 0), (4, 2), (2, 2), (2, 4), (0, 4)].into_iter().map(Point::from).collect()
+    }
+
+    #[test]
+    fn validate_picks_theorem_on_square() {
+        let acc = Accumulator::new(10, 2);
+        let square = square();
+        assert_eq!(twice_area(&square, &acc), 32);
+        assert_eq!(boundary_points(&square, &acc), 16);
+        assert_eq!(interior_points(&square, &acc), 9);
+    }
+
+    #[test]
+    fn validate_point_in_polygon() {
+        let square = square();
+        assert_eq!(point_in_polygon(&Point::new(2, 2), &square), Location::Inside);
+        assert_eq!(point_in_polygon(&Point::new(4, 2), &square), Location::Boundary);
+        assert_eq!(point_in_polygon(&Point::new(0, 0), &square), Location::Boundary);
+        assert_eq!(point_in_polygon(&Point::new(5, 2), &square), Location::Outside);
+        assert_eq!(point_in_polygon(&Point::new(-1, 4), &square), Location::Outside);
+    }
+
+    #[test]
+    fn validate_concave_polygon() {
+        let acc = Accumulator::new(10, 2);
+        let l_shape = l_shape();
+        assert_eq!(twice_area(&l_shape, &acc), 24);
+        assert_eq!(boundary_points(&l_shape, &acc), 16);
+        assert_eq!(interior_points(&l_shape, &acc), 5);
+
+        assert_eq!(point_in_polygon(&Point::new(1, 3), &l_shape), Location::Inside);
+        assert_eq!(point_in_polygon(&Point::new(3, 1), &l_shape), Location::Inside);
+        assert_eq!(point_in_polygon(&Point::new(3, 3), &l_shape), Location::Outside);
+        assert_eq!(point_in_polygon(&Point::new(2, 3), &l_shape), Location::Boundary);
+        assert_eq!(point_in_polygon(&Point::new(3, 2), &l_shape), Location::Boundary);
+    }
+
+    #[test]
+    fn validate_points_level_with_vertices() {
+        let l_shape = l_shape();
+        // Level with the reflex corner at (2, 2) and the corner at (4, 2)
+        assert_eq!(point_in_polygon(&Point::new(1, 2), &l_shape), Location::Inside);
+        assert_eq!(point_in_polygon(&Point::new(-1, 2), &l_shape), Location::Outside);
+        assert_eq!(point_in_polygon(&Point::new(5, 2), &l_shape), Location::Outside);
+        // Level with the top and bottom edges
+        assert_eq!(point_in_polygon(&Point::new(-1, 0), &l_shape), Location::Outside);
+        assert_eq!(point_in_polygon(&Point::new(3, 4), &l_shape), Location::Outside);
+        assert_eq!(point_in_polygon(&Point::new(5, 0), &l_shape), Location::Outside);
+    }
+
+    #[test]
+    fn validate_collinear_vertices() {
+        let acc = Accumulator::new(10, 2);
+        // The square again with extra vertices half way along two sides
+        let square = [(0, 0), (2, 0), (4, 0), (4, 2), (4, 4), (0, 4)]
+            .into_iter()
+            .map(Point::from)
+            .collect::<Vec<_>>();
+        assert_eq!(twice_area(&square, &acc), 32);
+        assert_eq!(boundary_points(&square, &acc), 16);
+        assert_eq!(interior_points(&square, &acc), 9);
+
+        assert_eq!(point_in_polygon(&Point::new(2, 2), &square), Location::Inside);
+        assert_eq!(point_in_polygon(&Point::new(3, 0), &square), Location::Boundary);
+        assert_eq!(point_in_polygon(&Point::new(2, 0), &square), Location::Boundary);
+        assert_eq!(point_in_polygon(&Point::new(5, 2), &square), Location::Outside);
+        assert_eq!(point_in_polygon(&Point::new(-1, 2), &square), Location::Outside);
+        assert_eq!(point_in_polygon(&Point::new(2, -1), &square), Location::Outside);
+    }
+}
@@ -14,6 +14,12 @@ pub mod day12;
 pub mod day13;
 pub mod day14;
 pub mod day15;
+pub mod checked;
+pub mod geometry;
+pub mod memo;
+pub mod report;
+
+use report::{ExplainError, OutputFormat};
 
 pub trait Solution {
     type ParsedInput;
@@ -32,7 +38,10 @@ pub trait Solution {
     }
 
     fn solve(input_lines: &str) -> (String, String) {
-        let mut input = Self::parse_input(input_lines);
+        Self::solve_parsed(Self::parse_input(input_lines))
+    }
+
+    fn solve_parsed(mut input: Self::ParsedInput) -> (String, String) {
         let p1 = Self::part_one(&mut input);
         let p2 = Self::part_two(&mut input);
         println!("----------");
@@ -41,14 +50,31 @@ pub trait Solution {
     }
 }
 
-pub fn solve_day(day: &i32) {
+// Settings from the command line for the days that can be configured.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub bag: Option<day02::Cubes>,
+    pub strict: bool,
+}
+
+pub fn solve_day(day: &i32, options: &Options) {
     match day {
         0 => day00::Day00::solve(include_str!("../inputs/0")),
         1 => day01::Day01::solve(include_str!("../inputs/1")),
-        2 => day02::Day02::solve(include_str!("../inputs/2")),
+        2 => {
+            let input = day02::Day02::parse_input(include_str!("../inputs/2"));
+            match &options.bag {
+                Some(bag) => day02::Day02::solve_parsed(input.with_bag(bag.clone())),
+                None => day02::Day02::solve_parsed(input),
+            }
+        }
         3 => day03::Day03::solve(include_str!("../inputs/3")),
         4 => day04::Day04::solve(include_str!("../inputs/4")),
-        5 => day05::Day05::solve(include_str!("../inputs/5")),
+        5 => {
+            let input = day05::Day05::parse_input(include_str!("../inputs/5"));
+            input.check(options.strict).unwrap_or_else(|err| panic!("{}", err));
+            day05::Day05::solve_parsed(input)
+        }
         6 => day06::Day06::solve(include_str!("../inputs/6")),
         7 => day07::Day07::solve(include_str!("../inputs/7")),
         8 => day08::Day08::solve(include_str!("../inputs/8")),
@@ -61,4 +87,17 @@ pub fn solve_day(day: &i32) {
         15 => day15::Day15::solve(include_str!("../inputs/15")),
         _ => panic!("Day not found"),
     };
+}
+
+pub fn explain_day(day: &i32, format: OutputFormat) -> Result<(), ExplainError> {
+    match day {
+        1 => day01::Day01::print_explanation(include_str!("../inputs/1"), format),
+        3 => day03::Day03::print_explanation(include_str!("../inputs/3"), format),
+        4 => day04::Day04::print_explanation(include_str!("../inputs/4"), format),
+        5 => day05::Day05::print_explanation(include_str!("../inputs/5"), format),
+        6 => day06::Day06::print_explanation(include_str!("../inputs/6"), format),
+        7 => day07::Day07::print_explanation(include_str!("../inputs/7"), format),
+        8 => day08::Day08::print_explanation(include_str!("../inputs/8"), format),
+        _ => Err(ExplainError::NoExplanation { day: *day }),
+    }
 }
\ No newline at end of file
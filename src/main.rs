@@ -1,14 +1,56 @@
-use aoc2023::solve_day;
-use clap::Parser;
+use aoc2023::{day02::Cubes, explain_day, report::OutputFormat, solve_day, Options};
+use clap::{error::ErrorKind, CommandFactory, Parser};
 
 #[derive(Parser)]
 #[command(author="Shaan Arora", version="0.1.0", about="Advent of Code 2023", long_about="None")]
 struct Cli {
-    day: Option<i32>
+    day: Option<i32>,
+
+    /// Print a breakdown of how the answer for the day was reached
+    #[arg(long, requires = "day")]
+    explain: bool,
+
+    /// Output format used by --explain
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Day 2 bag limits, e.g. "12 red, 13 green, 14 blue"
+    #[arg(long, conflicts_with = "bag_file")]
+    bag: Option<String>,
+
+    /// File containing the day 2 bag limits in the same format as --bag
+    #[arg(long)]
+    bag_file: Option<std::path::PathBuf>,
+
+    /// Treat overlapping ranges in the day 5 almanac as errors
+    #[arg(long)]
+    strict: bool,
 }
 
 fn main() {
     let cli = Cli::parse();
+    if cli.explain {
+        if let Err(err) = explain_day(&cli.day.unwrap(), cli.format) {
+            Cli::command().error(ErrorKind::InvalidValue, err).exit();
+        }
+        return;
+    }
+
+    let bag = match (cli.bag, cli.bag_file) {
+        (Some(bag), _) => Some(bag),
+        (None, Some(path)) => Some(std::fs::read_to_string(&path).unwrap_or_else(|err| {
+            let message = format!("Couldn't read {}: {}", path.display(), err);
+            Cli::command().error(ErrorKind::Io, message).exit()
+        })),
+        (None, None) => None,
+    }
+    .map(|bag| {
+        Cubes::parse(&bag).unwrap_or_else(|err| {
+            Cli::command().error(ErrorKind::InvalidValue, format!("Invalid bag, {}", err)).exit()
+        })
+    });
+    let options = Options { bag, strict: cli.strict };
+
     let mut days = (0..=25).collect::<Vec<i32>>();
     let mut days_to_execute = vec![];
     if let Some(day) = cli.day {
@@ -22,6 +64,6 @@ fn main() {
     }
 
     for day in days_to_execute {
-        solve_day(&day);
+        solve_day(&day, &options);
     }
 }